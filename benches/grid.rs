@@ -0,0 +1,62 @@
+//! Compares recomputing the revision of a RevisedGrid after a single edit
+//! against naively rehashing every cell. Run with `cargo bench --bench grid`.
+
+use std::{hash::Hasher, hint::black_box, time::Instant};
+
+use hashrevise::{Revisable, RevisedGrid, RevisionHash, RevisionHasher};
+
+struct Cell(u64);
+
+impl Revisable for Cell {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_u64(self.0);
+        hasher.into_revision()
+    }
+}
+
+const ROWS: usize = 500;
+const COLS: usize = 500;
+const EDITS: usize = 1000;
+const NAIVE_EDITS: usize = 50;
+
+fn naive_revision(grid: &RevisedGrid<Cell>) -> RevisionHash {
+    let mut hasher = RevisionHasher::new();
+    hasher.write_usize(grid.rows());
+    hasher.write_usize(grid.cols());
+    for row in 0..grid.rows() {
+        for col in 0..grid.cols() {
+            hasher.write_revisable(grid.get(row, col).unwrap());
+        }
+    }
+    hasher.into_revision()
+}
+
+fn main() {
+    let mut grid = RevisedGrid::from_fn(ROWS, COLS, |r, c| Cell((r * COLS + c) as u64));
+    assert_eq!(grid.get_revision(), naive_revision(&grid));
+
+    let start = Instant::now();
+    for i in 0..EDITS {
+        grid.get_mut(i % ROWS, (i * 7) % COLS).unwrap().0 += 1;
+        black_box(grid.get_revision());
+    }
+    let incremental = start.elapsed();
+
+    let start = Instant::now();
+    for i in 0..NAIVE_EDITS {
+        grid.get_mut(i % ROWS, (i * 7) % COLS).unwrap().0 += 1;
+        black_box(naive_revision(&grid));
+    }
+    let naive = start.elapsed();
+
+    assert_eq!(grid.get_revision(), naive_revision(&grid));
+
+    println!(
+        "{}x{} grid: incremental {:?}/edit, naive {:?}/edit",
+        ROWS,
+        COLS,
+        incremental / EDITS as u32,
+        naive / NAIVE_EDITS as u32
+    );
+}
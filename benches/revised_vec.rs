@@ -0,0 +1,68 @@
+//! Compares repeatedly computing the aggregate revision of a large
+//! RevisedVec against a RevisedSlice, which caches the aggregate, both
+//! when nothing changes between frames and after a single edit per frame.
+//! Run with `cargo bench --bench revised_vec`.
+
+use std::{hash::Hasher, hint::black_box, time::Instant};
+
+use hashrevise::{Revisable, Revised, RevisedSlice, RevisedVec, RevisionHash, RevisionHasher};
+
+struct Node(u64);
+
+impl Revisable for Node {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_u64(self.0);
+        hasher.into_revision()
+    }
+}
+
+const ITEMS: usize = 100_000;
+const FRAMES: usize = 200;
+
+fn main() {
+    let mut vec: RevisedVec<Node> = (0..ITEMS).map(|i| Revised::new(Node(i as u64))).collect();
+    let mut slice: RevisedSlice<Node> = (0..ITEMS).map(|i| Node(i as u64)).collect();
+    assert_eq!(vec.get_revision(), slice.get_revision());
+
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        black_box(black_box(&vec).get_revision());
+    }
+    let vec_unchanged = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        black_box(black_box(&slice).get_revision());
+    }
+    let slice_unchanged = start.elapsed();
+
+    let start = Instant::now();
+    for i in 0..FRAMES {
+        vec[(i * 7919) % ITEMS].0 += 1;
+        black_box(vec.get_revision());
+    }
+    let vec_edited = start.elapsed();
+
+    let start = Instant::now();
+    for i in 0..FRAMES {
+        slice.get_mut((i * 7919) % ITEMS).unwrap().0 += 1;
+        black_box(slice.get_revision());
+    }
+    let slice_edited = start.elapsed();
+
+    assert_eq!(vec.get_revision(), slice.get_revision());
+
+    println!(
+        "{} items, unchanged: RevisedVec {:?}/frame, RevisedSlice {:?}/frame",
+        ITEMS,
+        vec_unchanged / FRAMES as u32,
+        slice_unchanged / FRAMES as u32
+    );
+    println!(
+        "{} items, one edit: RevisedVec {:?}/frame, RevisedSlice {:?}/frame",
+        ITEMS,
+        vec_edited / FRAMES as u32,
+        slice_edited / FRAMES as u32
+    );
+}
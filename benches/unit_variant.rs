@@ -0,0 +1,35 @@
+//! Compares computing the revision of unit enum variants using
+//! RevisionHash::unit_variant against hashing the discriminant with a
+//! RevisionHasher. Run with `cargo bench --bench unit_variant`.
+
+use std::{hash::Hasher, hint::black_box, time::Instant};
+
+use hashrevise::{RevisionHash, RevisionHasher};
+
+const ITERATIONS: u32 = 10_000_000;
+
+fn with_hasher(discriminant: u32) -> RevisionHash {
+    let mut hasher = RevisionHasher::new();
+    hasher.write_u32(discriminant);
+    hasher.into_revision()
+}
+
+fn main() {
+    let start = Instant::now();
+    for i in 0..ITERATIONS {
+        black_box(with_hasher(black_box(i % 64)));
+    }
+    let hasher_time = start.elapsed();
+
+    let start = Instant::now();
+    for i in 0..ITERATIONS {
+        black_box(RevisionHash::unit_variant(black_box(i % 64)));
+    }
+    let fast_time = start.elapsed();
+
+    println!(
+        "unit variant revision: hasher {:?}/iter, unit_variant {:?}/iter",
+        hasher_time / ITERATIONS,
+        fast_time / ITERATIONS
+    );
+}
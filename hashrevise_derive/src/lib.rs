@@ -0,0 +1,773 @@
+//! Derive macro for the Revisable trait of the hashrevise crate. This is
+//! re-exported by hashrevise when its `derive` feature is enabled, and
+//! shouldn't need to be depended on directly.
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, quote_spanned};
+use syn::{
+    parse_macro_input, parse_quote, punctuated::Punctuated, spanned::Spanned, Attribute, Data,
+    DeriveInput, Error, ExprPath, Field, Fields, GenericArgument, Ident, Index, LitStr,
+    PathArguments, ReturnType, Token, Type, Variant, WherePredicate,
+};
+
+/// Derive Revisable for a struct or enum by hashing each of its fields in
+/// declaration order.
+///
+/// Structs, both with named fields and tuple structs, hash the revision of
+/// each field using a RevisionHasher. Enums first hash the index of the
+/// variant in declaration order as a u32, followed by the revisions of the
+/// variant's fields. Variants without any fields take a fast path and use
+/// RevisionHash::unit_variant with the variant's index instead. The two
+/// are computed by unrelated functions, so they're domain-separated and
+/// don't systematically collide, though like any two 64-bit hashes they
+/// can still collide by chance.
+///
+/// Every type parameter which appears in the type of a field that isn't
+/// skipped is required to implement Revisable, except where it only
+/// appears inside `PhantomData<..>`. When that's too strict,
+/// e.g. for a field of type `T::Key` where only the associated type is
+/// Revisable, the inferred bounds can be replaced on the type with
+/// `#[revise(bound = "T::Key: Revisable")]`. An empty string removes them.
+///
+/// A collection field whose order doesn't matter, such as a Vec which
+/// represents a set, can be marked `#[revise(order_independent)]`. The
+/// revisions of its elements are then combined like those of a HashSet,
+/// so that reordering them doesn't change the revision, although repeated
+/// elements still count. Any type whose references can be iterated over,
+/// yielding Revisable items, is supported.
+///
+/// A field holding bytes, such as a `Vec<u8>`, `Box<[u8]>` or any other
+/// type implementing `AsRef<[u8]>`, can be marked `#[revise(bytes)]`. It's
+/// then hashed as its length followed by its raw bytes in one write,
+/// instead of the revision of every byte. This suits e.g. a catch-all
+/// `Unknown(#[revise(bytes)] Vec<u8>)` variant holding the payloads of
+/// variants from newer versions, which can't collide with a known variant
+/// carrying the same bytes since the variant's index is hashed first. The
+/// field's type needn't be Revisable.
+///
+/// With `#[revise(variant_count_tag)]` on an enum, the number of variants
+/// is hashed before the variant's index, including for variants without
+/// fields. Adding or removing a variant then changes the revision of every
+/// value of the enum, which signals a schema change to caches persisted
+/// across versions. The tradeoff is that all such caches are invalidated
+/// whenever the enum grows, even for values whose variant is unchanged.
+///
+/// With `#[revise(domain = 0x6d65_7465_7273_0001)]` on a type, the derive
+/// also implements RevisableDomain with the given constant as its DOMAIN,
+/// and every revision of the type starts from
+/// RevisionHasher::with_domain(DOMAIN). Enum variants without fields then
+/// hash their index like the others instead of using the unit fast path.
+///
+/// A field can be excluded from the revision with `#[revise(skip)]`, e.g.
+/// for fields caching derived data or holding handles which aren't part
+/// of the object's contents.
+///
+/// A field holding a secret, such as an API key, can be marked
+/// `#[revise(redact)]`. Like a skipped field, it's left out of the
+/// revision, so that the revision can be logged or exposed externally.
+/// The derive then also generates two inherent methods: `full_revision`,
+/// which includes the redacted fields and suits internal caches, and
+/// `public_revision`, which is the same as `get_revision`. Unlike skipped
+/// fields, redacted fields need to be Revisable.
+///
+/// With `#[revise(field_revisions)]` on a struct, the derive also
+/// generates an inherent `field_revisions` method returning an array which
+/// pairs the name of each field that's part of the revision with a
+/// revision of that field alone. Tuple struct fields are named by their
+/// index. Comparing two such arrays with diff_fields gives the names of
+/// the fields that changed, e.g. for replicating only those.
+///
+/// All fields of a given type can be skipped at once with
+/// `#[revise(skip_type = "Span")]` on the type, which can be repeated for
+/// several types. This suits e.g. the nodes of a syntax tree whose source
+/// spans shouldn't affect their revisions. A type given as a single
+/// identifier also matches paths ending in it, such as `ast::Span`, but
+/// not other types containing it, such as `Option<Span>`. Such fields
+/// don't need their type to be Revisable, and fields with attributes of
+/// their own aren't skipped.
+///
+/// A field whose type doesn't implement Revisable can instead be hashed by
+/// a function with `#[revise(with = "path::to::function")]`. The function
+/// is called with a reference to the field and the `&mut RevisionHasher`
+/// of the object, and the field's type needn't be Revisable.
+///
+/// Likewise, a whole enum variant can be hashed by a function with
+/// `#[revise(variant_with = "path::to::function")]` on the variant. The
+/// variant's index is still hashed first, and the function is then called
+/// with a reference to each of the variant's fields in declaration order,
+/// followed by the `&mut RevisionHasher`. None of the variant's fields need
+/// to be Revisable, and they can't have attributes of their own.
+///
+/// ```
+/// use hashrevise::{Revisable, RevisionHash};
+///
+/// #[derive(Revisable)]
+/// struct Document {
+///     title: Vec<u8>,
+///     version: u32,
+///     #[revise(skip)]
+///     layout_cache: Option<Vec<f32>>,
+/// }
+///
+/// #[derive(Revisable)]
+/// enum Shape {
+///     Empty,
+///     Circle(i32),
+///     Rect { width: i32, height: i32 },
+/// }
+///
+/// assert_eq!(Shape::Empty.get_revision(), RevisionHash::unit_variant(0));
+/// ```
+///
+/// Type parameters which are only used by skipped fields are unbounded:
+///
+/// ```
+/// use hashrevise::Revisable;
+///
+/// struct Handle;
+///
+/// #[derive(Revisable)]
+/// struct Cached<T, C> {
+///     value: T,
+///     #[revise(skip)]
+///     cache: C,
+/// }
+///
+/// Cached { value: 1, cache: Handle }.get_revision();
+/// ```
+///
+/// A type parameter used by a field must be Revisable wherever the type's
+/// revision is needed:
+///
+/// ```compile_fail
+/// use hashrevise::Revisable;
+///
+/// struct Handle;
+///
+/// #[derive(Revisable)]
+/// struct Wrapper<T> {
+///     inner: T,
+/// }
+///
+/// Wrapper { inner: Handle }.get_revision();
+/// ```
+///
+/// ```
+/// use hashrevise::{Revisable, RevisionHasher};
+/// use std::hash::Hasher;
+///
+/// struct Buffer {
+///     id: u64,
+/// }
+///
+/// fn hash_buffer(buffer: &Buffer, hasher: &mut RevisionHasher) {
+///     hasher.write_u64(buffer.id);
+/// }
+///
+/// #[derive(Revisable)]
+/// struct Mesh {
+///     vertices: Vec<f32>,
+///     #[revise(with = "hash_buffer")]
+///     buffer: Buffer,
+/// }
+///
+/// let mesh = Mesh { vertices: vec![], buffer: Buffer { id: 7 } };
+/// mesh.get_revision();
+/// ```
+///
+/// A field whose type doesn't implement Revisable is reported at the
+/// field, with a message naming its type and suggesting `skip` and `with`:
+///
+/// ```compile_fail
+/// use hashrevise::Revisable;
+///
+/// struct Handle;
+///
+/// #[derive(Revisable)]
+/// struct Texture {
+///     id: u32,
+///     handle: Handle,
+/// }
+/// ```
+#[proc_macro_derive(Revisable, attributes(revise))]
+pub fn derive_revisable(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// The options given to a field through #[revise(...)] attributes
+#[derive(Default)]
+struct FieldOptions {
+    /// Whether the field is left out of the revision
+    skip: bool,
+
+    /// Whether the field is left out of the revision but included in the
+    /// full revision
+    redact: bool,
+
+    /// Whether the field is hashed as raw bytes
+    bytes: bool,
+
+    /// The function hashing the field instead of its Revisable
+    /// implementation, if given
+    with: Option<ExprPath>,
+
+    /// Whether the field's elements are combined regardless of their order
+    order_independent: bool,
+}
+
+/// The options given to a type through #[revise(...)] attributes
+#[derive(Default)]
+struct ContainerOptions {
+    /// The where-predicates replacing the inferred bounds, if given
+    bound: Option<Punctuated<WherePredicate, Token![,]>>,
+
+    /// Whether an enum hashes its number of variants before the variant
+    variant_count_tag: bool,
+
+    /// The types whose fields are all left out of the revision
+    skip_types: Vec<Type>,
+
+    /// The RevisableDomain constant to hash first, if given
+    domain: Option<syn::Expr>,
+
+    /// Whether a struct gets a field_revisions method
+    field_revisions: bool,
+}
+
+impl ContainerOptions {
+    fn parse(attrs: &[Attribute]) -> syn::Result<ContainerOptions> {
+        let mut options = ContainerOptions::default();
+        for attr in attrs {
+            if !attr.path().is_ident("revise") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("bound") {
+                    let bound: LitStr = meta.value()?.parse()?;
+                    options.bound = Some(bound.parse_with(Punctuated::parse_terminated)?);
+                    Ok(())
+                } else if meta.path.is_ident("variant_count_tag") {
+                    options.variant_count_tag = true;
+                    Ok(())
+                } else if meta.path.is_ident("skip_type") {
+                    let ty: LitStr = meta.value()?.parse()?;
+                    options.skip_types.push(ty.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("domain") {
+                    options.domain = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("field_revisions") {
+                    options.field_revisions = true;
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    Err(meta.error("`skip` is only supported on fields"))
+                } else {
+                    Err(meta.error(
+                        "unsupported revise attribute, \
+                         expected `bound`, `variant_count_tag`, `skip_type`, `domain` \
+                         or `field_revisions`",
+                    ))
+                }
+            })?;
+        }
+        Ok(options)
+    }
+
+    /// Returns true if fields of the given type are skipped through
+    /// `skip_type`. A type matches if it's written the same way, or if the
+    /// skipped type is a single identifier such as `Span` and the field's
+    /// type is a path ending in that identifier, such as `ast::Span`.
+    fn skips_type(&self, ty: &Type) -> bool {
+        self.skip_types.iter().any(|skipped| {
+            if quote!(#skipped).to_string() == quote!(#ty).to_string() {
+                return true;
+            }
+            let (Type::Path(skipped), Type::Path(ty)) = (skipped, ty) else {
+                return false;
+            };
+            let Some(ident) = skipped.path.get_ident() else {
+                return false;
+            };
+            ty.qself.is_none()
+                && ty
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == *ident && segment.arguments.is_empty())
+        })
+    }
+}
+
+impl FieldOptions {
+    /// Parse the options of a field. A field without options of its own
+    /// whose type is skipped through the container's `skip_type` is
+    /// skipped as well.
+    fn parse(field: &Field, container: &ContainerOptions) -> syn::Result<FieldOptions> {
+        let mut options = FieldOptions::default();
+        for attr in &field.attrs {
+            if !attr.path().is_ident("revise") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    options.skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("redact") {
+                    options.redact = true;
+                    Ok(())
+                } else if meta.path.is_ident("with") {
+                    let with: LitStr = meta.value()?.parse()?;
+                    options.with = Some(with.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("order_independent") {
+                    options.order_independent = true;
+                    Ok(())
+                } else if meta.path.is_ident("bytes") {
+                    options.bytes = true;
+                    Ok(())
+                } else {
+                    Err(meta.error(
+                        "unsupported revise attribute, \
+                         expected `skip`, `redact`, `with`, `order_independent` or `bytes`",
+                    ))
+                }
+            })?;
+        }
+        let modes = [
+            options.skip,
+            options.redact,
+            options.with.is_some(),
+            options.order_independent,
+            options.bytes,
+        ];
+        if modes.iter().filter(|m| **m).count() > 1 {
+            return Err(Error::new_spanned(
+                field,
+                "only one of `skip`, `redact`, `with`, `order_independent` \
+                 and `bytes` can be used on a field",
+            ));
+        }
+        if !modes.contains(&true) && container.skips_type(&field.ty) {
+            options.skip = true;
+        }
+        Ok(options)
+    }
+}
+
+/// The options given to an enum variant through #[revise(...)] attributes
+#[derive(Default)]
+struct VariantOptions {
+    /// The function hashing the variant's fields instead of their
+    /// Revisable implementations, if given
+    with: Option<ExprPath>,
+}
+
+impl VariantOptions {
+    fn parse(variant: &Variant) -> syn::Result<VariantOptions> {
+        let mut options = VariantOptions::default();
+        for attr in &variant.attrs {
+            if !attr.path().is_ident("revise") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("variant_with") {
+                    let with: LitStr = meta.value()?.parse()?;
+                    options.with = Some(with.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported revise attribute, expected `variant_with`"))
+                }
+            })?;
+        }
+        if options.with.is_some() {
+            for field in &variant.fields {
+                if let Some(attr) = field.attrs.iter().find(|a| a.path().is_ident("revise")) {
+                    return Err(Error::new_spanned(
+                        attr,
+                        "fields of a variant using `variant_with` can't have revise attributes",
+                    ));
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn expand(mut input: DeriveInput) -> syn::Result<TokenStream> {
+    let options = ContainerOptions::parse(&input.attrs)?;
+
+    let predicates: Vec<WherePredicate> = match &options.bound {
+        Some(bound) => bound.iter().cloned().collect(),
+        None => {
+            let field_types = revised_field_types(&input.data, &options)?;
+            input
+                .generics
+                .type_params()
+                .map(|p| &p.ident)
+                .filter(|param| field_types.iter().any(|ty| uses_ident(ty, param)))
+                .map(|param| parse_quote!(#param: ::hashrevise::Revisable))
+                .collect()
+        }
+    };
+    input
+        .generics
+        .make_where_clause()
+        .predicates
+        .extend(predicates);
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = revision_body(name, &input.data, &options, false)?;
+
+    let redacted_impl = if has_redacted_fields(&input.data, &options)? {
+        let vis = &input.vis;
+        let full_body = revision_body(name, &input.data, &options, true)?;
+        Some(quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// Get the revision of every field, including those marked
+                /// #[revise(redact)]
+                #vis fn full_revision(&self) -> ::hashrevise::RevisionHash {
+                    #full_body
+                }
+
+                /// Get the revision without the fields marked
+                /// #[revise(redact)], which is the same as get_revision
+                #vis fn public_revision(&self) -> ::hashrevise::RevisionHash {
+                    ::hashrevise::Revisable::get_revision(self)
+                }
+            }
+        })
+    } else {
+        None
+    };
+
+    let domain_impl = options.domain.as_ref().map(|domain| {
+        quote! {
+            impl #impl_generics ::hashrevise::RevisableDomain for #name #ty_generics #where_clause {
+                const DOMAIN: u64 = #domain;
+            }
+        }
+    });
+
+    let field_revisions_impl = if options.field_revisions {
+        let Data::Struct(data) = &input.data else {
+            return Err(Error::new(
+                Span::call_site(),
+                "`field_revisions` is only supported on structs",
+            ));
+        };
+        let vis = &input.vis;
+        let mut entries = Vec::new();
+        for (i, field) in data.fields.iter().enumerate() {
+            let (member, field_name) = match &field.ident {
+                Some(ident) => (quote!(#ident), ident.to_string()),
+                None => {
+                    let index = Index::from(i);
+                    (quote!(#index), i.to_string())
+                }
+            };
+            if let Some(write) = write_field(field, &options, quote!(&self.#member), false)? {
+                entries.push(quote! {
+                    (#field_name, {
+                        let mut hasher = ::hashrevise::RevisionHasher::new();
+                        #write
+                        hasher.into_revision()
+                    })
+                });
+            }
+        }
+        let count = entries.len();
+        Some(quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// Get the names of the fields which are part of the
+                /// revision, each paired with a revision of that field alone
+                #vis fn field_revisions(
+                    &self,
+                ) -> [(&'static str, ::hashrevise::RevisionHash); #count] {
+                    [#(#entries,)*]
+                }
+            }
+        })
+    } else {
+        None
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::hashrevise::Revisable for #name #ty_generics #where_clause {
+            fn get_revision(&self) -> ::hashrevise::RevisionHash {
+                #body
+            }
+        }
+
+        #domain_impl
+
+        #redacted_impl
+
+        #field_revisions_impl
+    })
+}
+
+/// Generate the body of a method computing the revision of the type. The
+/// fields marked `redact` are only included if `redacted` is true.
+fn revision_body(
+    name: &Ident,
+    data: &Data,
+    options: &ContainerOptions,
+    redacted: bool,
+) -> syn::Result<TokenStream> {
+    let new_hasher = match &options.domain {
+        Some(_) => quote! {
+            ::hashrevise::RevisionHasher::with_domain(
+                <Self as ::hashrevise::RevisableDomain>::DOMAIN,
+            )
+        },
+        None => quote!(::hashrevise::RevisionHasher::new()),
+    };
+
+    let body = match data {
+        Data::Struct(data) => {
+            if options.variant_count_tag {
+                return Err(Error::new(
+                    Span::call_site(),
+                    "`variant_count_tag` is only supported on enums",
+                ));
+            }
+            let mut writes = Vec::new();
+            for (i, field) in data.fields.iter().enumerate() {
+                let member = match &field.ident {
+                    Some(ident) => quote!(#ident),
+                    None => {
+                        let index = Index::from(i);
+                        quote!(#index)
+                    }
+                };
+                if let Some(write) = write_field(field, options, quote!(&self.#member), redacted)? {
+                    writes.push(write);
+                }
+            }
+            quote! {
+                #[allow(unused_mut)]
+                let mut hasher = #new_hasher;
+                #(#writes)*
+                hasher.into_revision()
+            }
+        }
+        Data::Enum(data) => {
+            let count = u32::try_from(data.variants.len())
+                .map_err(|_| Error::new_spanned(name, "too many variants"))?;
+            let count_tag = if options.variant_count_tag {
+                Some(quote!(::core::hash::Hasher::write_u32(&mut hasher, #count);))
+            } else {
+                None
+            };
+
+            let mut arms = Vec::new();
+            for (i, variant) in data.variants.iter().enumerate() {
+                let variant_name = &variant.ident;
+                let index = u32::try_from(i)
+                    .map_err(|_| Error::new_spanned(variant, "too many variants"))?;
+                let variant_options = VariantOptions::parse(variant)?;
+
+                if variant.fields.is_empty()
+                    && count_tag.is_none()
+                    && options.domain.is_none()
+                    && variant_options.with.is_none()
+                {
+                    arms.push(quote! {
+                        Self::#variant_name { .. } => ::hashrevise::RevisionHash::unit_variant(#index),
+                    });
+                    continue;
+                }
+
+                let mut bindings = Vec::new();
+                let mut writes = Vec::new();
+                let mut args = Vec::new();
+                for (j, field) in variant.fields.iter().enumerate() {
+                    let binding = format_ident!("__revise_field{}", j);
+                    let member = match (&variant.fields, &field.ident) {
+                        (Fields::Named(_), Some(ident)) => quote!(#ident),
+                        _ => {
+                            let index = Index::from(j);
+                            quote!(#index)
+                        }
+                    };
+                    if variant_options.with.is_some() {
+                        bindings.push(quote!(#member: #binding));
+                        args.push(binding);
+                    } else if let Some(write) =
+                        write_field(field, options, quote!(#binding), redacted)?
+                    {
+                        bindings.push(quote!(#member: #binding));
+                        writes.push(write);
+                    }
+                }
+                if let Some(with) = &variant_options.with {
+                    writes.push(quote_spanned! {with.span()=>
+                        #with(#(#args,)* &mut hasher);
+                    });
+                }
+                arms.push(quote! {
+                    Self::#variant_name { #(#bindings,)* .. } => {
+                        let mut hasher = #new_hasher;
+                        #count_tag
+                        ::core::hash::Hasher::write_u32(&mut hasher, #index);
+                        #(#writes)*
+                        hasher.into_revision()
+                    }
+                });
+            }
+            if arms.is_empty() {
+                quote!(match *self {})
+            } else {
+                quote! {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new(
+                Span::call_site(),
+                "Revisable can't be derived for unions",
+            ));
+        }
+    };
+    Ok(body)
+}
+
+/// Returns true if any field of the type is marked `redact`
+fn has_redacted_fields(data: &Data, container: &ContainerOptions) -> syn::Result<bool> {
+    let fields: Vec<&Field> = match data {
+        Data::Struct(data) => data.fields.iter().collect(),
+        Data::Enum(data) => data.variants.iter().flat_map(|v| &v.fields).collect(),
+        Data::Union(_) => Vec::new(),
+    };
+    for field in fields {
+        if FieldOptions::parse(field, container)?.redact {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Get the types of all fields which are hashed through their own
+/// Revisable implementation, i.e. which aren't skipped, hashed with a
+/// function or hashed as bytes
+fn revised_field_types(data: &Data, container: &ContainerOptions) -> syn::Result<Vec<Type>> {
+    let fields: Vec<&Field> = match data {
+        Data::Struct(data) => data.fields.iter().collect(),
+        Data::Enum(data) => {
+            let mut fields = Vec::new();
+            for variant in &data.variants {
+                if VariantOptions::parse(variant)?.with.is_none() {
+                    fields.extend(&variant.fields);
+                }
+            }
+            fields
+        }
+        Data::Union(_) => Vec::new(),
+    };
+    let mut types = Vec::new();
+    for field in fields {
+        let options = FieldOptions::parse(field, container)?;
+        if !options.skip && options.with.is_none() && !options.bytes {
+            types.push(field.ty.clone());
+        }
+    }
+    Ok(types)
+}
+
+/// Returns true if the given identifier appears in a type, e.g. whether a
+/// type parameter is used by a field's type. Uses inside `PhantomData<..>`
+/// don't count, since PhantomData is Revisable for any type parameter.
+fn uses_ident(ty: &Type, ident: &Ident) -> bool {
+    match ty {
+        Type::Path(path) => {
+            if let Some(qself) = &path.qself {
+                if uses_ident(&qself.ty, ident) {
+                    return true;
+                }
+            }
+            let segments = &path.path.segments;
+            if segments.last().is_some_and(|s| s.ident == "PhantomData") {
+                return false;
+            }
+            if path.qself.is_none()
+                && path.path.leading_colon.is_none()
+                && segments.first().is_some_and(|s| s.ident == *ident)
+            {
+                return true;
+            }
+            segments.iter().any(|segment| match &segment.arguments {
+                PathArguments::None => false,
+                PathArguments::AngleBracketed(args) => args.args.iter().any(|arg| match arg {
+                    GenericArgument::Type(ty) => uses_ident(ty, ident),
+                    GenericArgument::AssocType(assoc) => uses_ident(&assoc.ty, ident),
+                    other => tokens_use_ident(quote!(#other), ident),
+                }),
+                PathArguments::Parenthesized(args) => {
+                    args.inputs.iter().any(|ty| uses_ident(ty, ident))
+                        || matches!(&args.output, ReturnType::Type(_, ty) if uses_ident(ty, ident))
+                }
+            })
+        }
+        Type::Reference(reference) => uses_ident(&reference.elem, ident),
+        Type::Slice(slice) => uses_ident(&slice.elem, ident),
+        Type::Array(array) => uses_ident(&array.elem, ident),
+        Type::Ptr(ptr) => uses_ident(&ptr.elem, ident),
+        Type::Group(group) => uses_ident(&group.elem, ident),
+        Type::Paren(paren) => uses_ident(&paren.elem, ident),
+        Type::Tuple(tuple) => tuple.elems.iter().any(|ty| uses_ident(ty, ident)),
+        other => tokens_use_ident(quote!(#other), ident),
+    }
+}
+
+/// Returns true if the given identifier appears anywhere in a token stream
+fn tokens_use_ident(tokens: TokenStream, ident: &Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(i) => i == *ident,
+        TokenTree::Group(g) => tokens_use_ident(g.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Generate the statement writing a field's revision to the hasher, given
+/// an expression referencing the field, or None if the field is skipped,
+/// or is redacted and `redacted` is false. The statement is spanned to the
+/// field's type, so that a missing Revisable implementation is reported at
+/// the field.
+fn write_field(
+    field: &Field,
+    container: &ContainerOptions,
+    value: TokenStream,
+    redacted: bool,
+) -> syn::Result<Option<TokenStream>> {
+    let options = FieldOptions::parse(field, container)?;
+    if options.skip || (options.redact && !redacted) {
+        return Ok(None);
+    }
+    if let Some(with) = &options.with {
+        return Ok(Some(quote_spanned! {with.span()=>
+            #with(#value, &mut hasher);
+        }));
+    }
+    let ty = &field.ty;
+    if options.order_independent {
+        return Ok(Some(quote_spanned! {ty.span()=>
+            ::hashrevise::__private::write_unordered_field::<#ty>(&mut hasher, #value);
+        }));
+    }
+    if options.bytes {
+        return Ok(Some(quote_spanned! {ty.span()=>
+            ::hashrevise::__private::write_bytes_field::<#ty>(&mut hasher, #value);
+        }));
+    }
+    Ok(Some(quote_spanned! {ty.span()=>
+        ::hashrevise::__private::write_field::<#ty>(&mut hasher, #value);
+    }))
+}
@@ -0,0 +1,9 @@
+//! Checks the compile errors reported by the derive macro. Run with
+//! `TRYBUILD=overwrite` to update the expected output after changing a
+//! message.
+
+#[test]
+fn ui() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
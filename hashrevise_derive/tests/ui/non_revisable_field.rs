@@ -0,0 +1,11 @@
+use hashrevise::Revisable;
+
+struct Handle;
+
+#[derive(Revisable)]
+struct Texture {
+    id: u32,
+    handle: Handle,
+}
+
+fn main() {}
@@ -0,0 +1,100 @@
+use alloc::vec::Vec;
+use core::cell::Cell;
+
+use crate::{Revisable, RevisionHash, SequenceHasher};
+
+/// AppendLog is an append-only sequence of Revisable items, such as an
+/// event log, whose RevisionHash is maintained incrementally. Appending an
+/// item hashes only that item in O(1), and the log's RevisionHash remains
+/// equal to that of its items hashed as a slice.
+///
+/// Because [T] hashes its length before its items, the revision of the
+/// whole log can't be carried forward from one append to the next. It's
+/// instead finalized on read from the stored revisions of the items,
+/// without hashing any item again, and is cached until the next append.
+pub struct AppendLog<T> {
+    /// The items, in the order they were appended
+    items: Vec<T>,
+
+    /// The revisions of all items so far
+    hasher: SequenceHasher,
+
+    /// The revision of the whole log, if it's up to date
+    revision: Cell<Option<RevisionHash>>,
+}
+
+impl<T: Revisable> AppendLog<T> {
+    /// Construct a new, empty AppendLog
+    pub fn new() -> AppendLog<T> {
+        AppendLog {
+            items: Vec::new(),
+            hasher: SequenceHasher::new(),
+            revision: Cell::new(None),
+        }
+    }
+
+    /// Append an item to the end of the log
+    pub fn push(&mut self, item: T) {
+        self.hasher.push(item.get_revision());
+        self.items.push(item);
+        self.revision.set(None);
+    }
+
+    /// Get the number of items
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns true if there are no items
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Get the item at the given position
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.items.get(index)
+    }
+
+    /// Get all items as a slice, oldest first
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Iterate over the items, oldest first
+    pub fn iter(&self) -> core::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Get the RevisionHash of the whole log. This combines the stored
+    /// revisions of all items the first time after an append, and is O(1)
+    /// until the next append.
+    pub fn get_revision(&self) -> RevisionHash {
+        if let Some(revision) = self.revision.get() {
+            return revision;
+        }
+        let revision = self.hasher.finish();
+        self.revision.set(Some(revision));
+        revision
+    }
+}
+
+impl<T: Revisable> Default for AppendLog<T> {
+    fn default() -> AppendLog<T> {
+        AppendLog::new()
+    }
+}
+
+impl<T: Revisable> Extend<T> for AppendLog<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+/// AppendLog<T> is Revisable using its cached revision
+impl<T: Revisable> Revisable for AppendLog<T> {
+    fn get_revision(&self) -> RevisionHash {
+        AppendLog::get_revision(self)
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    cell::Cell,
+    ops::{Deref, DerefMut},
+    sync::mpsc::Sender,
+};
+
+use crate::{Revisable, RevisionHash};
+
+/// ChannelRevised is like Revised<T>, except that it reports changes of
+/// the stored object's RevisionHash over a channel. Whenever the revision
+/// is recomputed after the object was mutably accessed (or for the first
+/// time), and the result differs from the last revision that was sent,
+/// the new revision is sent to the channel. Since the revision is only
+/// computed lazily, a change is reported when it's first observed through
+/// get_revision rather than at the time of the mutation, and mutations
+/// that don't change the revision aren't reported at all.
+///
+/// Send errors, i.e. a disconnected receiver, are ignored.
+pub struct ChannelRevised<T> {
+    /// The stored object
+    value: T,
+
+    /// The revision hash of the stored object, if it's up to date
+    revision: Cell<Option<RevisionHash>>,
+
+    /// The revision hash which was last sent, if any
+    last_sent: Cell<Option<RevisionHash>>,
+
+    /// The channel to which new revisions are sent
+    sender: Sender<RevisionHash>,
+}
+
+impl<T: Revisable> ChannelRevised<T> {
+    /// Construct a new ChannelRevised object containing the given object,
+    /// which sends new revisions using the given sender
+    pub fn new(value: T, sender: Sender<RevisionHash>) -> ChannelRevised<T> {
+        ChannelRevised {
+            value,
+            revision: Cell::new(None),
+            last_sent: Cell::new(None),
+            sender,
+        }
+    }
+
+    /// Get the contained object's RevisionHash, computing it if it's not
+    /// cached and sending it to the channel if it changed
+    pub fn get_revision(&self) -> RevisionHash {
+        match self.revision.get() {
+            Some(v) => v,
+            None => {
+                let v = self.value.get_revision();
+                self.revision.set(Some(v));
+                if self.last_sent.get() != Some(v) {
+                    self.last_sent.set(Some(v));
+                    let _ = self.sender.send(v);
+                }
+                v
+            }
+        }
+    }
+
+    /// Consume the ChannelRevised and return the contained object
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/// ChannelRevised<T> can deref to &T
+impl<T: Revisable> Deref for ChannelRevised<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+/// ChannelRevised<T> can deref to &mut T, which invalidates the cached
+/// revision
+impl<T: Revisable> DerefMut for ChannelRevised<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.revision.set(None);
+        &mut self.value
+    }
+}
+
+/// ChannelRevised<T> is Revisable using its cached revision
+impl<T: Revisable> Revisable for ChannelRevised<T> {
+    fn get_revision(&self) -> RevisionHash {
+        ChannelRevised::get_revision(self)
+    }
+}
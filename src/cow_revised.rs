@@ -0,0 +1,95 @@
+use alloc::sync::Arc;
+use core::{
+    cell::Cell,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{Revisable, RevisionHash};
+
+/// CowRevised is like Revised<T>, except that the stored object is shared
+/// through an Arc<T> with copy-on-write semantics. Cloning a CowRevised
+/// only bumps a reference count and copies the cached revision, making
+/// snapshots cheap. The stored object is only deep-cloned when it's
+/// mutably accessed while shared with another snapshot, at which point
+/// only the mutated copy's cached revision is invalidated.
+///
+/// The RevisionHash of a CowRevised<T> is the same as that of a
+/// Revised<T> with the same contents.
+pub struct CowRevised<T> {
+    /// The stored object, possibly shared with other snapshots
+    value: Arc<T>,
+
+    /// The revision hash of the stored object, if it's up to date
+    revision: Cell<Option<RevisionHash>>,
+}
+
+impl<T: Revisable> CowRevised<T> {
+    /// Construct a new CowRevised object containing the given object
+    pub fn new(value: T) -> CowRevised<T> {
+        CowRevised {
+            value: Arc::new(value),
+            revision: Cell::new(None),
+        }
+    }
+
+    /// Get the contained object's RevisionHash. If the object is
+    /// not mutated, this will compute the RevisionHash only once
+    /// and cache it for reuse.
+    pub fn get_revision(&self) -> RevisionHash {
+        match self.revision.get() {
+            Some(v) => v,
+            None => {
+                let v = self.value.get_revision();
+                self.revision.set(Some(v));
+                v
+            }
+        }
+    }
+
+    /// Returns true if both objects share the same stored object
+    pub fn ptr_eq(this: &CowRevised<T>, other: &CowRevised<T>) -> bool {
+        Arc::ptr_eq(&this.value, &other.value)
+    }
+
+    /// Returns true if the stored object is shared with another snapshot,
+    /// meaning that the next mutable access will deep-clone it
+    pub fn is_shared(this: &CowRevised<T>) -> bool {
+        Arc::strong_count(&this.value) > 1
+    }
+}
+
+/// Cloning a CowRevised<T> shares the stored object and its cached
+/// revision rather than cloning the object itself
+impl<T> Clone for CowRevised<T> {
+    fn clone(&self) -> Self {
+        CowRevised {
+            value: Arc::clone(&self.value),
+            revision: self.revision.clone(),
+        }
+    }
+}
+
+/// CowRevised<T> can deref to &T
+impl<T: Revisable> Deref for CowRevised<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+/// CowRevised<T> can deref to &mut T, which clones the stored object
+/// first if it's shared
+impl<T: Revisable + Clone> DerefMut for CowRevised<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.revision.set(None);
+        Arc::make_mut(&mut self.value)
+    }
+}
+
+/// CowRevised<T> is Revisable using its cached revision
+impl<T: Revisable> Revisable for CowRevised<T> {
+    fn get_revision(&self) -> RevisionHash {
+        CowRevised::get_revision(self)
+    }
+}
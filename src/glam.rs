@@ -0,0 +1,65 @@
+use std::hash::Hasher;
+
+use glam::{
+    DMat2, DMat3, DMat4, DQuat, DVec2, DVec3, DVec4, IVec2, IVec3, IVec4, Mat2, Mat3, Mat3A, Mat4,
+    Quat, UVec2, UVec3, UVec4, Vec2, Vec3, Vec3A, Vec4,
+};
+
+use crate::{canonical_f32_bits, canonical_f64_bits, Revisable, RevisionHash, RevisionHasher};
+
+fn write_f32(hasher: &mut RevisionHasher, x: f32) {
+    hasher.write_u32(canonical_f32_bits(x));
+}
+
+fn write_f64(hasher: &mut RevisionHasher, x: f64) {
+    hasher.write_u64(canonical_f64_bits(x));
+}
+
+fn write_i32(hasher: &mut RevisionHasher, x: i32) {
+    hasher.write_i32(x);
+}
+
+fn write_u32(hasher: &mut RevisionHasher, x: u32) {
+    hasher.write_u32(x);
+}
+
+/// Helper macro for implementing Revisable for glam types by hashing
+/// their components in order, as given by the named array conversion
+macro_rules! glam_impl {
+    ($typename: ident, $to_array: ident, $write_fn: ident) => {
+        impl Revisable for $typename {
+            fn get_revision(&self) -> RevisionHash {
+                let mut hasher = RevisionHasher::new();
+                for x in self.$to_array() {
+                    $write_fn(&mut hasher, x);
+                }
+                hasher.into_revision()
+            }
+        }
+    };
+}
+
+glam_impl!(Vec2, to_array, write_f32);
+glam_impl!(Vec3, to_array, write_f32);
+glam_impl!(Vec3A, to_array, write_f32);
+glam_impl!(Vec4, to_array, write_f32);
+glam_impl!(Quat, to_array, write_f32);
+glam_impl!(Mat2, to_cols_array, write_f32);
+glam_impl!(Mat3, to_cols_array, write_f32);
+glam_impl!(Mat3A, to_cols_array, write_f32);
+glam_impl!(Mat4, to_cols_array, write_f32);
+
+glam_impl!(DVec2, to_array, write_f64);
+glam_impl!(DVec3, to_array, write_f64);
+glam_impl!(DVec4, to_array, write_f64);
+glam_impl!(DQuat, to_array, write_f64);
+glam_impl!(DMat2, to_cols_array, write_f64);
+glam_impl!(DMat3, to_cols_array, write_f64);
+glam_impl!(DMat4, to_cols_array, write_f64);
+
+glam_impl!(IVec2, to_array, write_i32);
+glam_impl!(IVec3, to_array, write_i32);
+glam_impl!(IVec4, to_array, write_i32);
+glam_impl!(UVec2, to_array, write_u32);
+glam_impl!(UVec3, to_array, write_u32);
+glam_impl!(UVec4, to_array, write_u32);
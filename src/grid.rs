@@ -0,0 +1,164 @@
+use alloc::vec::Vec;
+use core::{cell::Cell, hash::Hasher};
+
+use crate::{sequence_revision, Revisable, Revised, RevisionHash, RevisionHasher};
+
+/// RevisedGrid is a fixed-size two-dimensional grid of Revised cells, such
+/// as a spreadsheet, which caches the revision of each cell, row and column
+/// as well as that of the whole grid. Editing a single cell only
+/// invalidates that cell, its row, its column, and the grid, so that
+/// recomputing the grid's RevisionHash after an edit rehashes the contents
+/// of one cell and otherwise reuses the cached revisions of all others.
+///
+/// The grid's RevisionHash is the same as hashing its number of rows and
+/// columns followed by the RevisionHash of every cell in row-major order.
+/// Recombining the cached cell revisions is a single pass writing one u64
+/// per cell, which is cheap compared to hashing the cells' contents but
+/// still proportional to the number of cells. Row and column revisions
+/// aren't part of the grid's revision and only serve row_revision and
+/// col_revision.
+pub struct RevisedGrid<T> {
+    /// The cells in row-major order
+    cells: Vec<Revised<T>>,
+
+    /// The number of rows
+    rows: usize,
+
+    /// The number of columns
+    cols: usize,
+
+    /// The revision of each row, if it's up to date. These are only used
+    /// by row_revision and not by the grid's revision.
+    row_revisions: Vec<Cell<Option<RevisionHash>>>,
+
+    /// The revision of each column, if it's up to date. These are only
+    /// used by col_revision and not by the grid's revision.
+    col_revisions: Vec<Cell<Option<RevisionHash>>>,
+
+    /// The revision of the whole grid, if it's up to date
+    revision: Cell<Option<RevisionHash>>,
+}
+
+impl<T: Revisable> RevisedGrid<T> {
+    /// Construct a new RevisedGrid with the given dimensions, where each
+    /// cell is initialized by calling f(row, col)
+    pub fn from_fn<F>(rows: usize, cols: usize, mut f: F) -> RevisedGrid<T>
+    where
+        F: FnMut(usize, usize) -> T,
+    {
+        let mut cells = Vec::with_capacity(rows * cols);
+        for row in 0..rows {
+            for col in 0..cols {
+                cells.push(Revised::new(f(row, col)));
+            }
+        }
+        RevisedGrid {
+            cells,
+            rows,
+            cols,
+            row_revisions: (0..rows).map(|_| Cell::new(None)).collect(),
+            col_revisions: (0..cols).map(|_| Cell::new(None)).collect(),
+            revision: Cell::new(None),
+        }
+    }
+
+    /// Get the number of rows
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Get the number of columns
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Get the cell at the given row and column, if it's in bounds
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        self.index(row, col).map(|i| &*self.cells[i])
+    }
+
+    /// Get mutable access to the cell at the given row and column, if it's
+    /// in bounds. This invalidates the cached revisions of the cell, its
+    /// row, its column, and the grid.
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+        let i = self.index(row, col)?;
+        self.row_revisions[row].set(None);
+        self.col_revisions[col].set(None);
+        self.revision.set(None);
+        Some(&mut *self.cells[i])
+    }
+
+    /// Replace the cell at the given row and column. Panics if the
+    /// position is out of bounds.
+    pub fn set(&mut self, row: usize, col: usize, value: T) {
+        *self
+            .get_mut(row, col)
+            .expect("RevisedGrid position out of bounds") = value;
+    }
+
+    /// Get the RevisionHash of the given row, which is the same as
+    /// hashing its cells as a slice. Panics if the row is out of bounds.
+    pub fn row_revision(&self, row: usize) -> RevisionHash {
+        let cache = &self.row_revisions[row];
+        match cache.get() {
+            Some(v) => v,
+            None => {
+                let cells = &self.cells[(row * self.cols)..((row + 1) * self.cols)];
+                let v = sequence_revision(cells.iter().map(|c| c.get_revision()));
+                cache.set(Some(v));
+                v
+            }
+        }
+    }
+
+    /// Get the RevisionHash of the given column, which is the same as
+    /// hashing its cells from top to bottom as a slice. Panics if the
+    /// column is out of bounds.
+    pub fn col_revision(&self, col: usize) -> RevisionHash {
+        let cache = &self.col_revisions[col];
+        match cache.get() {
+            Some(v) => v,
+            None => {
+                let cells = self.cells[col..].iter().step_by(self.cols);
+                let v = sequence_revision(cells.map(|c| c.get_revision()));
+                cache.set(Some(v));
+                v
+            }
+        }
+    }
+
+    /// Get the RevisionHash of the whole grid. This is cached until a cell
+    /// is mutated, and is then recomputed from the cached cell revisions.
+    pub fn get_revision(&self) -> RevisionHash {
+        match self.revision.get() {
+            Some(v) => v,
+            None => {
+                let mut hasher = RevisionHasher::new();
+                hasher.write_usize(self.rows);
+                hasher.write_usize(self.cols);
+                for cell in &self.cells {
+                    hasher.write_revision(cell.get_revision());
+                }
+                let v = hasher.into_revision();
+                self.revision.set(Some(v));
+                v
+            }
+        }
+    }
+
+    /// Helper method for getting the row-major index of a cell
+    fn index(&self, row: usize, col: usize) -> Option<usize> {
+        if row < self.rows && col < self.cols {
+            Some(row * self.cols + col)
+        } else {
+            None
+        }
+    }
+}
+
+/// RevisedGrid<T> is Revisable using its cached revision
+impl<T: Revisable> Revisable for RevisedGrid<T> {
+    fn get_revision(&self) -> RevisionHash {
+        RevisedGrid::get_revision(self)
+    }
+}
@@ -1,16 +1,140 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::{
+    borrow::{Borrow, BorrowMut},
+    cell::{Cell, RefCell},
+    fmt,
+    future::Future,
+    hash::{BuildHasher, Hash, Hasher},
+    marker::PhantomData,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    ops::{Deref, DerefMut, Range, RangeFrom, RangeInclusive, RangeTo, RangeToInclusive},
+    time::Duration,
+};
+
+#[cfg(feature = "alloc")]
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    format,
+    rc::Rc,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+
+#[cfg(feature = "std")]
 use std::{
-    cell::Cell,
-    collections::HashMap,
-    hash::Hasher,
-    ops::{BitXor, Deref, DerefMut},
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod append_log;
+#[cfg(feature = "std")]
+mod channel_revised;
+#[cfg(feature = "alloc")]
+mod cow_revised;
+#[cfg(feature = "alloc")]
+mod grid;
+#[cfg(feature = "alloc")]
+mod merkle;
+#[cfg(feature = "alloc")]
+mod revised_cache;
+#[cfg(feature = "alloc")]
+mod revised_notify;
+mod revision128;
+#[cfg(feature = "std")]
+mod revision_cache;
+#[cfg(feature = "alloc")]
+mod ring;
+#[cfg(feature = "alloc")]
+mod row;
+#[cfg(feature = "alloc")]
+mod shared_revised;
+#[cfg(feature = "alloc")]
+mod slice;
+mod sync_revised;
+#[cfg(feature = "std")]
+pub mod tracking;
+
+#[cfg(feature = "glam")]
+mod glam;
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra;
+
+#[cfg(feature = "rayon")]
+mod rayon;
+
+#[cfg(feature = "serde")]
+mod serde;
+
+#[cfg(feature = "serde_json")]
+mod serde_json;
+
+#[cfg(all(test, feature = "std"))]
 mod test;
 
+// Allow the derive macro's generated code to refer to ::hashrevise from
+// within this crate too
+extern crate self as hashrevise;
+
+#[cfg(feature = "derive")]
+pub use hashrevise_derive::Revisable;
+
+#[cfg(feature = "rayon")]
+pub use rayon::par_revision;
+
+#[cfg(feature = "alloc")]
+pub use append_log::AppendLog;
+#[cfg(feature = "std")]
+pub use channel_revised::ChannelRevised;
+#[cfg(feature = "alloc")]
+pub use cow_revised::CowRevised;
+#[cfg(feature = "alloc")]
+pub use grid::RevisedGrid;
+#[cfg(feature = "alloc")]
+pub use merkle::MerkleRevised;
+#[cfg(feature = "alloc")]
+pub use revised_cache::RevisedCache;
+#[cfg(feature = "alloc")]
+pub use revised_notify::RevisedNotify;
+pub use revision128::{Revisable128, RevisionHash128, RevisionHasher128};
+#[cfg(feature = "std")]
+pub use revision_cache::RevisionCache;
+#[cfg(feature = "alloc")]
+pub use ring::RevisedRing;
+#[cfg(feature = "alloc")]
+pub use row::RowRevisionBuilder;
+#[cfg(feature = "alloc")]
+pub use shared_revised::SharedRevised;
+#[cfg(feature = "alloc")]
+pub use slice::RevisedSlice;
+pub use sync_revised::SyncRevised;
+
 /// RevisionHasher is an efficient hasher used to compute revision hashes.
-pub struct RevisionHasher {
-    hasher: seahash::SeaHasher,
+/// By default it's backed by SeaHasher. A different underlying Hasher, e.g.
+/// one with a fixed seed or a cryptographic one, can be used with
+/// RevisionHasher::with_hasher in hand-written Revisable implementations.
+///
+/// Only hand-written hashing can pick the hasher type. Revisable has no
+/// hasher parameter, so the crate's built-in implementations (primitives,
+/// strings, collections, tuples, and the derive macro) always use the
+/// default SeaHasher. A custom hasher only affects the bytes written
+/// directly to it. The revisions of child objects come from their own
+/// implementations, and a custom hasher only combines them.
+/// The revisions produced by different underlying hashers are unrelated, so
+/// mixing hasher types within a tree of Revisable objects gives different
+/// revisions than using a single hasher type throughout.
+#[derive(Clone)]
+pub struct RevisionHasher<H: Hasher = seahash::SeaHasher> {
+    hasher: H,
 }
 
 impl RevisionHasher {
@@ -21,6 +145,37 @@ impl RevisionHasher {
         }
     }
 
+    /// Construct a new RevisionHasher for a RevisableDomain. A nonzero
+    /// domain is hashed first, while a domain of zero gives the same
+    /// result as RevisionHasher::new.
+    pub fn with_domain(domain: u64) -> RevisionHasher {
+        let mut hasher = RevisionHasher::new();
+        if domain != 0 {
+            hasher.write_u64(domain);
+        }
+        hasher
+    }
+
+    /// Construct a new RevisionHasher whose underlying SeaHasher starts
+    /// from the given seeds instead of the default ones. Hashers with
+    /// distinct seeds give unrelated revisions for the same contents, so
+    /// starting with a seed unique to each subsystem keeps the revisions
+    /// of their objects apart even when they hash identical bytes.
+    pub fn with_seed(a: u64, b: u64, c: u64, d: u64) -> RevisionHasher {
+        RevisionHasher {
+            hasher: seahash::SeaHasher::with_seeds(a, b, c, d),
+        }
+    }
+}
+
+impl<H: Hasher> RevisionHasher<H> {
+    /// Construct a new RevisionHasher using the given underlying hasher.
+    /// Revisions written to it with write_revisable are still computed by
+    /// the objects' own Revisable implementations.
+    pub fn with_hasher(hasher: H) -> RevisionHasher<H> {
+        RevisionHasher { hasher }
+    }
+
     /// Recursively hash another object and write its resulting
     /// RevisionHash
     pub fn write_revisable<T: Revisable>(&mut self, t: &T) {
@@ -29,33 +184,263 @@ impl RevisionHasher {
 
     /// Hash the RevisionHash of another object
     pub fn write_revision(&mut self, r: RevisionHash) {
-        self.hasher.write_u64(r.value());
+        self.write_u64(r.value());
+    }
+
+    /// Hash a sequence of objects in the same way as the [T] implementation
+    /// does, by writing the number of objects followed by the RevisionHash
+    /// of each object in order. Hashing a slice's items on their own with a
+    /// new RevisionHasher thus gives the same revision as the slice.
+    ///
+    /// This relies on the iterator's size_hint to know the number of
+    /// objects up front, which is exact for any ExactSizeIterator. For
+    /// other iterators, such as filtered ones, the objects are counted as
+    /// they're written and the count is written after them instead, which
+    /// no longer matches the [T] implementation. Collect such objects
+    /// first if the revision must match that of a slice.
+    pub fn write_iter<I>(&mut self, iter: I)
+    where
+        I: IntoIterator,
+        I::Item: Revisable,
+    {
+        let iter = iter.into_iter();
+        match iter.size_hint() {
+            (lower, Some(upper)) if lower == upper => {
+                self.write_usize(lower);
+                let mut count: usize = 0;
+                for item in iter {
+                    self.write_revision(item.get_revision());
+                    count += 1;
+                }
+                debug_assert_eq!(count, lower, "iterator reported the wrong length");
+            }
+            _ => {
+                let mut count: usize = 0;
+                for item in iter {
+                    self.write_revision(item.get_revision());
+                    count += 1;
+                }
+                self.write_usize(count);
+            }
+        }
+    }
+
+    /// Hash the items of a slice in the same way as the [T] implementation
+    /// does, as with write_iter. Unlike write_revisable, which writes the
+    /// slice's RevisionHash as a single value, this writes the items
+    /// directly, so a new RevisionHasher gives exactly the slice's revision.
+    pub fn write_revisable_slice<T: Revisable>(&mut self, items: &[T]) {
+        self.write_iter(items);
+    }
+
+    /// Hash an optional object, writing the same RevisionHash as the
+    /// Option<T> implementation, which tags whether a value is present.
+    /// Returns self so that calls can be chained.
+    pub fn write_some<T: Revisable>(&mut self, opt: &Option<T>) -> &mut Self {
+        self.write_revision(opt.get_revision());
+        self
+    }
+
+    /// Hash a fixed tag such as a subsystem name or format version, for
+    /// domain separation inside a custom Revisable implementation. The tag
+    /// is prefixed with its length, so it can't run together with what's
+    /// written after it. Writing e.g. "layout_v2" first and changing it
+    /// whenever the format changes gives the type all new revisions.
+    pub fn write_tag(&mut self, tag: &'static str) {
+        self.write_usize(tag.len());
+        self.write(tag.as_bytes());
     }
 
     /// Consume the RevisionHasher and return its final RevisionHash
     /// which summarizes the contents it has seen
     pub fn into_revision(self) -> RevisionHash {
-        RevisionHash::new(self.hasher.finish())
+        RevisionHash::avoid_null(self.hasher.finish())
+    }
+}
+
+impl Default for RevisionHasher {
+    fn default() -> RevisionHasher {
+        RevisionHasher::new()
+    }
+}
+
+/// Helper macro for implementing the integer-writing methods of Hasher
+/// using explicit little-endian byte order. The default methods of Hasher
+/// use the platform's native byte order instead, which would make
+/// revisions differ between little- and big-endian platforms. Each integer
+/// is first converted to the given width, so that usize and isize can be
+/// written as 64 bits on every platform, and lengths hash the same on
+/// 32-bit targets such as wasm as they do on 64-bit targets.
+macro_rules! le_write_impl {
+    ($($write_fn: ident: $int: ty => $wide: ty),*) => {
+        $(
+            fn $write_fn(&mut self, i: $int) {
+                self.write(&(i as $wide).to_le_bytes());
+            }
+        )*
+    };
+}
+
+pub(crate) use le_write_impl;
+
+impl<H: Hasher> Hasher for RevisionHasher<H> {
+    fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.hasher.write(bytes);
+    }
+
+    le_write_impl!(
+        write_u8: u8 => u8,
+        write_u16: u16 => u16,
+        write_u32: u32 => u32,
+        write_u64: u64 => u64,
+        write_u128: u128 => u128,
+        write_usize: usize => u64,
+        write_i8: i8 => i8,
+        write_i16: i16 => i16,
+        write_i32: i32 => i32,
+        write_i64: i64 => i64,
+        write_i128: i128 => i128,
+        write_isize: isize => i64
+    );
+}
+
+/// InstrumentedHasher is a drop-in replacement for RevisionHasher which
+/// produces identical revisions but also tallies how many writes and how
+/// many bytes flow through it. It's useful for profiling which parts of a
+/// data structure dominate the cost of computing its revision.
+#[derive(Default)]
+pub struct InstrumentedHasher {
+    /// The underlying hasher
+    hasher: RevisionHasher,
+
+    /// The number of write calls so far
+    writes: usize,
+
+    /// The total number of bytes written so far
+    bytes: usize,
+}
+
+impl InstrumentedHasher {
+    /// Construct a new InstrumentedHasher
+    pub fn new() -> InstrumentedHasher {
+        InstrumentedHasher {
+            hasher: RevisionHasher::new(),
+            writes: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Recursively hash another object and write its resulting
+    /// RevisionHash
+    pub fn write_revisable<T: Revisable>(&mut self, t: &T) {
+        self.write_revision(t.get_revision());
+    }
+
+    /// Hash the RevisionHash of another object
+    pub fn write_revision(&mut self, r: RevisionHash) {
+        self.write_u64(r.value());
+    }
+
+    /// Get the number of writes so far
+    pub fn write_count(&self) -> usize {
+        self.writes
+    }
+
+    /// Get the total number of bytes written so far
+    pub fn bytes_written(&self) -> usize {
+        self.bytes
+    }
+
+    /// Consume the InstrumentedHasher and return its final RevisionHash
+    /// which summarizes the contents it has seen
+    pub fn into_revision(self) -> RevisionHash {
+        self.hasher.into_revision()
     }
 }
 
-impl Hasher for RevisionHasher {
+impl Hasher for InstrumentedHasher {
     fn finish(&self) -> u64 {
         self.hasher.finish()
     }
 
     fn write(&mut self, bytes: &[u8]) {
+        self.writes += 1;
+        self.bytes += bytes.len();
         self.hasher.write(bytes);
     }
+
+    le_write_impl!(
+        write_u8: u8 => u8,
+        write_u16: u16 => u16,
+        write_u32: u32 => u32,
+        write_u64: u64 => u64,
+        write_u128: u128 => u128,
+        write_usize: usize => u64,
+        write_i8: i8 => i8,
+        write_i16: i16 => i16,
+        write_i32: i32 => i32,
+        write_i64: i64 => i64,
+        write_i128: i128 => i128,
+        write_isize: isize => i64
+    );
+}
+
+/// RevisionBuildHasher is a BuildHasher which produces RevisionHashers,
+/// such that HashMap<K, V, RevisionBuildHasher> and similar collections
+/// hash their keys the same way that revisions are hashed. Note that all
+/// RevisionHashers start from the same fixed state, so unlike the default
+/// hasher of HashMap, this offers no resistance to denial-of-service
+/// attacks using keys chosen to collide.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct RevisionBuildHasher;
+
+impl BuildHasher for RevisionBuildHasher {
+    type Hasher = RevisionHasher;
+
+    fn build_hasher(&self) -> RevisionHasher {
+        RevisionHasher::new()
+    }
 }
 
 /// RevisionHash is an integer summary of the contents of a data structure,
 /// based on hashing, intended to be used in distinguishing whether data
 /// structures have changed or not.
+///
+/// All integers are hashed in little-endian byte order, so the revisions
+/// computed by this crate's Revisable implementations are the same on
+/// little- and big-endian platforms, and can be compared between them.
+/// Likewise, usize and isize are always hashed as 64-bit integers, so
+/// revisions are the same on 32- and 64-bit platforms.
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub struct RevisionHash(u64);
 
 impl RevisionHash {
+    /// A reserved RevisionHash which is never the result of hashing
+    /// anything, for use as a sentinel meaning e.g. "never computed".
+    /// RevisionHasher::into_revision and RevisionHash::unit_variant never
+    /// return it, and neither do the Revisable implementations built on
+    /// them.
+    pub const NULL: RevisionHash = RevisionHash(0);
+
+    /// Returns true if this is RevisionHash::NULL
+    pub fn is_null(&self) -> bool {
+        *self == RevisionHash::NULL
+    }
+
+    /// Map a hash value which happens to be the value of NULL to another
+    /// value, so that real revisions never equal NULL
+    const fn avoid_null(value: u64) -> RevisionHash {
+        if value == RevisionHash::NULL.0 {
+            RevisionHash(0x6a09_e667_f3bc_c908)
+        } else {
+            RevisionHash(value)
+        }
+    }
+
     /// Create a new RevisionHash with the given integer value
     pub fn new(value: u64) -> RevisionHash {
         RevisionHash(value)
@@ -65,11 +450,89 @@ impl RevisionHash {
     pub fn value(&self) -> u64 {
         self.0
     }
+
+    /// Combine several RevisionHashes into one, depending on their order.
+    /// This gives the same result as the revision of a slice of objects
+    /// with the given revisions.
+    pub fn combine<I>(hashes: I) -> RevisionHash
+    where
+        I: IntoIterator<Item = RevisionHash>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        sequence_revision(hashes.into_iter())
+    }
+
+    /// Combine several RevisionHashes into one, regardless of their order.
+    /// This gives the same result as the revision of a set of objects with
+    /// the given revisions, and equal hashes don't cancel each other out.
+    pub fn combine_unordered<I: IntoIterator<Item = RevisionHash>>(hashes: I) -> RevisionHash {
+        set_revision(hashes.into_iter())
+    }
+
+    /// Get the RevisionHash of a unit enum variant (one without fields),
+    /// directly derived from its discriminant. This is a fast path for
+    /// enums with many unit variants which avoids constructing a
+    /// RevisionHasher, and it can be evaluated at compile time. Variants
+    /// with fields should hash their discriminant followed by their fields
+    /// with a RevisionHasher as usual, which won't systematically collide
+    /// with the revisions of unit variants.
+    pub const fn unit_variant(discriminant: u32) -> RevisionHash {
+        // splitmix64 finalizer, offset so that discriminant 0 isn't fixed
+        let mut x = (discriminant as u64) ^ 0x9e37_79b9_7f4a_7c15;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        RevisionHash::avoid_null(x ^ (x >> 31))
+    }
+}
+
+/// RevisionHash is displayed as `0x` followed by 16 lowercase hexadecimal
+/// digits, such that all revisions are displayed with the same width
+impl fmt::Display for RevisionHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#018x}", self.0)
+    }
+}
+
+/// RevisionHash can be formatted as its integer value in lowercase
+/// hexadecimal, with the usual formatting options
+impl fmt::LowerHex for RevisionHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+/// RevisionHash can be formatted as its integer value in uppercase
+/// hexadecimal, with the usual formatting options
+impl fmt::UpperHex for RevisionHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+/// A RevisionHash can be created from its integer value, the same as
+/// with RevisionHash::new
+impl From<u64> for RevisionHash {
+    fn from(value: u64) -> RevisionHash {
+        RevisionHash::new(value)
+    }
+}
+
+/// A RevisionHash can be converted into its integer value, the same as
+/// with RevisionHash::value
+impl From<RevisionHash> for u64 {
+    fn from(revision: RevisionHash) -> u64 {
+        revision.value()
+    }
 }
 
 /// Revisable is a trait for types for which a RevisionHash can be computed.
 /// Something that implements Revisable can have changes to its contents
 /// tracked by watching its RevisionHash alone.
+///
+/// Revisable is object safe, so `dyn Revisable` (as well as e.g.
+/// `dyn Revisable + Send + Sync`) implements Revisable itself, and
+/// heterogeneous collections such as `Vec<Box<dyn Revisable>>` are
+/// Revisable through the blanket implementations for smart pointers.
 pub trait Revisable {
     /// Compute the RevisionHash of the object's contents. This should hash
     /// together everything that is relevant to the meaning of the object's
@@ -77,11 +540,104 @@ pub trait Revisable {
     /// exact same result if the object is unchanged or has been changed to
     /// something which is semantically identical.
     fn get_revision(&self) -> RevisionHash;
+
+    /// Get the object's RevisionHash as cheaply as possible. For types
+    /// which cache their revision, such as Revised<T>, this returns the
+    /// cached revision without touching the contents whenever the cache
+    /// is valid. The result is always the same as that of get_revision,
+    /// which this defaults to.
+    fn fast_revision(&self) -> RevisionHash {
+        self.get_revision()
+    }
+
+    /// Get the object's RevisionHash formatted as a quoted hexadecimal
+    /// string, suitable for use as an HTTP ETag header. The result can be
+    /// turned back into a RevisionHash with parse_etag.
+    #[cfg(feature = "alloc")]
+    fn etag(&self) -> String {
+        format!("\"{:016x}\"", self.get_revision().value())
+    }
+}
+
+/// RevisableDomain lets a Revisable type opt into a namespace of its own.
+/// A type with a nonzero DOMAIN should start hashing its contents with
+/// RevisionHasher::with_domain(Self::DOMAIN), so that it can't collide with
+/// a different type whose contents happen to be hashed the same way. Pick a
+/// random constant per type; unlike TypeId, it stays stable across
+/// compilers and builds. A DOMAIN of zero, the default, leaves the
+/// revision unchanged.
+pub trait RevisableDomain: Revisable {
+    /// The domain separator mixed into the type's revisions, or zero for none
+    const DOMAIN: u64 = 0;
+}
+
+/// Parse an HTTP ETag as produced by Revisable::etag back into a
+/// RevisionHash. Weak ETags (prefixed with `W/`) are also accepted.
+/// Returns None if the string isn't a quoted hexadecimal number.
+pub fn parse_etag(etag: &str) -> Option<RevisionHash> {
+    let etag = etag.trim();
+    let etag = etag.strip_prefix("W/").unwrap_or(etag);
+    let digits = etag.strip_prefix('"')?.strip_suffix('"')?;
+    if digits.is_empty() || digits.len() > 16 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(digits, 16).ok().map(RevisionHash::new)
+}
+
+/// Assert at compile time that the listed fields are exactly the fields of
+/// a struct, e.g. the fields which a hand-written get_revision hashes. The
+/// macro expands to an exhaustive destructuring pattern, so adding a field
+/// to the struct without adding it to the list (and presumably to
+/// get_revision too) becomes a compile error.
+///
+/// ```
+/// use hashrevise::{assert_revisable_fields, Revisable, RevisionHash, RevisionHasher};
+///
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// assert_revisable_fields!(Point { x, y });
+///
+/// impl Revisable for Point {
+///     fn get_revision(&self) -> RevisionHash {
+///         let mut hasher = RevisionHasher::new();
+///         hasher.write_revisable(&self.x);
+///         hasher.write_revisable(&self.y);
+///         hasher.into_revision()
+///     }
+/// }
+/// ```
+///
+/// Forgetting a field fails to compile:
+///
+/// ```compile_fail
+/// use hashrevise::assert_revisable_fields;
+///
+/// struct Point {
+///     x: i32,
+///     y: i32,
+///     z: i32,
+/// }
+///
+/// assert_revisable_fields!(Point { x, y });
+/// ```
+#[macro_export]
+macro_rules! assert_revisable_fields {
+    ($typename: path { $($field: ident),* $(,)? }) => {
+        const _: () = {
+            #[allow(dead_code)]
+            fn assert_revisable_fields(value: &$typename) {
+                let $typename { $($field: _),* } = value;
+            }
+        };
+    };
 }
 
 /// Helper macro for abbreviating Revisable implementations for plain types
 macro_rules! revisable_impl {
-    ($typename: ident, $hasher_fn: expr) => {
+    ($typename: ty, $hasher_fn: expr) => {
         impl Revisable for $typename {
             fn get_revision(&self) -> RevisionHash {
                 let mut hasher = RevisionHasher::new();
@@ -118,6 +674,42 @@ revisable_impl!(i128, |hasher: &mut RevisionHasher, x: &i128| hasher
     .write_i128(*x));
 revisable_impl!(isize, |hasher: &mut RevisionHasher, x: &isize| hasher
     .write_isize(*x));
+// Floats are canonicalized first, so that all NaNs have the same revision
+// and so do -0.0 and +0.0. Use TotalOrdFloat to tell them apart instead.
+revisable_impl!(f32, |hasher: &mut RevisionHasher, x: &f32| hasher
+    .write_u32(canonical_f32_bits(*x)));
+revisable_impl!(f64, |hasher: &mut RevisionHasher, x: &f64| hasher
+    .write_u64(canonical_f64_bits(*x)));
+revisable_impl!(char, |hasher: &mut RevisionHasher, x: &char| hasher
+    .write_u32(*x as u32));
+revisable_impl!((), |_: &mut RevisionHasher, _: &()| ());
+
+/// PhantomData carries no data, so it has the same constant revision as ()
+/// regardless of its type parameter
+impl<T: ?Sized> Revisable for PhantomData<T> {
+    fn get_revision(&self) -> RevisionHash {
+        ().get_revision()
+    }
+}
+
+/// str hashes its length followed by its UTF-8 bytes, so that adjacent
+/// strings can't run into one another, e.g. ("ab", "") and ("a", "b")
+impl Revisable for str {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_usize(self.len());
+        hasher.write(self.as_bytes());
+        hasher.into_revision()
+    }
+}
+
+/// String has the same revision as its contents as a str
+#[cfg(feature = "alloc")]
+impl Revisable for String {
+    fn get_revision(&self) -> RevisionHash {
+        self.as_str().get_revision()
+    }
+}
 
 /// Blanket implementation for references
 impl<T> Revisable for &T
@@ -127,173 +719,1157 @@ where
     fn get_revision(&self) -> RevisionHash {
         T::get_revision(self)
     }
+
+    fn fast_revision(&self) -> RevisionHash {
+        T::fast_revision(self)
+    }
 }
 
-/// Blanket implementation for 1-tuples
-impl<T> Revisable for (T,)
+/// Blanket implementation for Box, including unsized contents such
+/// as Box<[T]> and Box<dyn Revisable>
+#[cfg(feature = "alloc")]
+impl<T> Revisable for Box<T>
 where
-    T: Revisable,
+    T: Revisable + ?Sized,
 {
     fn get_revision(&self) -> RevisionHash {
-        self.0.get_revision()
+        T::get_revision(self)
+    }
+
+    fn fast_revision(&self) -> RevisionHash {
+        T::fast_revision(self)
     }
 }
 
-/// Blanket implementation for 2-tuples
-impl<T0, T1> Revisable for (T0, T1)
+/// Blanket implementation for Rc, which hashes the shared contents
+#[cfg(feature = "alloc")]
+impl<T> Revisable for Rc<T>
 where
-    T0: Revisable,
-    T1: Revisable,
+    T: Revisable + ?Sized,
 {
     fn get_revision(&self) -> RevisionHash {
-        let mut hasher = RevisionHasher::new();
-        hasher.write_revision(self.0.get_revision());
-        hasher.write_revision(self.1.get_revision());
-        RevisionHash::new(hasher.finish())
+        T::get_revision(self)
+    }
+
+    fn fast_revision(&self) -> RevisionHash {
+        T::fast_revision(self)
     }
 }
 
-/// Blanket implementation for 3-tuples
-impl<T0, T1, T2> Revisable for (T0, T1, T2)
+/// Blanket implementation for Arc, which hashes the shared contents
+#[cfg(feature = "alloc")]
+impl<T> Revisable for Arc<T>
 where
-    T0: Revisable,
-    T1: Revisable,
-    T2: Revisable,
+    T: Revisable + ?Sized,
 {
     fn get_revision(&self) -> RevisionHash {
-        let mut hasher = RevisionHasher::new();
-        hasher.write_revision(self.0.get_revision());
-        hasher.write_revision(self.1.get_revision());
-        hasher.write_revision(self.2.get_revision());
-        RevisionHash::new(hasher.finish())
+        T::get_revision(self)
+    }
+
+    fn fast_revision(&self) -> RevisionHash {
+        T::fast_revision(self)
     }
 }
 
-/// Blanket implementation for 4-tuples
-impl<T0, T1, T2, T3> Revisable for (T0, T1, T2, T3)
+/// Blanket implementation for Cell, which hashes a copy of the current
+/// contents as returned by Cell::get
+impl<T> Revisable for Cell<T>
 where
-    T0: Revisable,
-    T1: Revisable,
-    T2: Revisable,
-    T3: Revisable,
+    T: Revisable + Copy,
 {
     fn get_revision(&self) -> RevisionHash {
-        let mut hasher = RevisionHasher::new();
-        hasher.write_revision(self.0.get_revision());
-        hasher.write_revision(self.1.get_revision());
-        hasher.write_revision(self.2.get_revision());
-        hasher.write_revision(self.3.get_revision());
-        RevisionHash::new(hasher.finish())
+        self.get().get_revision()
     }
 }
 
-/// Blanket implementation for 5-tuples
-impl<T0, T1, T2, T3, T4> Revisable for (T0, T1, T2, T3, T4)
+/// Blanket implementation for RefCell, which hashes the current contents.
+/// This borrows the contents immutably and so, like RefCell::borrow, it
+/// panics if the contents are currently mutably borrowed. Computing the
+/// revision of a structure containing a RefCell while holding a borrow_mut
+/// of that RefCell is a bug and will fail loudly rather than producing a
+/// RevisionHash of partially-updated contents.
+impl<T> Revisable for RefCell<T>
 where
-    T0: Revisable,
-    T1: Revisable,
-    T2: Revisable,
-    T3: Revisable,
-    T4: Revisable,
+    T: Revisable + ?Sized,
 {
     fn get_revision(&self) -> RevisionHash {
-        let mut hasher = RevisionHasher::new();
-        hasher.write_revision(self.0.get_revision());
-        hasher.write_revision(self.1.get_revision());
-        hasher.write_revision(self.2.get_revision());
-        hasher.write_revision(self.3.get_revision());
-        hasher.write_revision(self.4.get_revision());
-        RevisionHash::new(hasher.finish())
+        self.borrow().get_revision()
     }
 }
 
-/// Revised is a wrapper struct for efficiently tracking the RevisionHash of
-/// a desired type T. The RevisionHash is computed lazily and is only
-/// recomputed when the object has been accessed mutably. This is achieved
-/// transparently using the Deref and DerefMut traits such that Revised<T>
-/// behaves in code just like a plain old T, except that computing its
-/// RevisionHash is optimized to avoid redundant recursions through all its
-/// contents to compute hash values, at the cost of a little extra storage.
-#[derive(Clone)]
-pub struct Revised<T> {
-    /// The stored object
-    value: T,
-
-    /// The revision hash of the stored object, if it's up to date
-    revision: Cell<Option<RevisionHash>>,
-}
-
-impl<T: Revisable> Revised<T> {
-    /// Construct a new Revised object containing the given object
-    pub fn new(value: T) -> Revised<T> {
-        Revised {
-            value,
-            revision: Cell::new(None),
+/// Blanket implementation for Option. None and Some are distinguished
+/// by a tag byte, so that Some(x) doesn't share the revision of x.
+impl<T> Revisable for Option<T>
+where
+    T: Revisable,
+{
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        match self {
+            None => hasher.write_u8(0x0),
+            Some(value) => {
+                hasher.write_u8(0x1);
+                hasher.write_revision(value.get_revision());
+            }
         }
+        hasher.into_revision()
     }
+}
 
-    /// Get the contained object's RevisionHash. If the object is
-    /// not mutated, this will compute the RevisionHash only once
-    /// and cache it for reuse.
-    pub fn get_revision(&self) -> RevisionHash {
-        match self.revision.get() {
-            Some(v) => v,
-            None => {
-                let v = self.value.get_revision();
-                self.revision.set(Some(v));
-                v
+/// Blanket implementation for Result. Ok and Err are distinguished by a
+/// tag byte, so that Ok(x) and Err(x) differ even if both types are equal.
+impl<T, E> Revisable for Result<T, E>
+where
+    T: Revisable,
+    E: Revisable,
+{
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        match self {
+            Ok(value) => {
+                hasher.write_u8(0x0);
+                hasher.write_revision(value.get_revision());
+            }
+            Err(error) => {
+                hasher.write_u8(0x1);
+                hasher.write_revision(error.get_revision());
             }
         }
+        hasher.into_revision()
     }
 }
 
-/// Revised<T> can deref to &T
-impl<T: Revisable> Deref for Revised<T> {
-    type Target = T;
-
-    fn deref(&self) -> &Self::Target {
-        &self.value
+/// Duration is hashed as its whole seconds followed by its nanoseconds
+impl Revisable for Duration {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_u64(self.as_secs());
+        hasher.write_u32(self.subsec_nanos());
+        hasher.into_revision()
     }
 }
 
-/// Revised<T> can deref to &mut T
-impl<T: Revisable> DerefMut for Revised<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.revision.set(None);
-        &mut self.value
+/// SystemTime is hashed as its offset from the unix epoch, along with a
+/// tag for whether it's before or after the epoch
+#[cfg(feature = "std")]
+impl Revisable for SystemTime {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(after) => {
+                hasher.write_u8(0x0);
+                hasher.write_revision(after.get_revision());
+            }
+            Err(before) => {
+                hasher.write_u8(0x1);
+                hasher.write_revision(before.duration().get_revision());
+            }
+        }
+        hasher.into_revision()
     }
 }
 
-/// Revised<T> is Revisable (obviously?)
-impl<T: Revisable> Revisable for Revised<T> {
+/// Ipv4Addr is hashed as its four octets
+impl Revisable for Ipv4Addr {
     fn get_revision(&self) -> RevisionHash {
-        Revised::get_revision(&self)
+        let mut hasher = RevisionHasher::new();
+        hasher.write(&self.octets());
+        hasher.into_revision()
     }
 }
 
-/// [T] where T is Revisable is also Revisable
-impl<T> Revisable for [T]
-where
-    T: Revisable,
-{
+/// Ipv6Addr is hashed as its sixteen octets
+impl Revisable for Ipv6Addr {
     fn get_revision(&self) -> RevisionHash {
         let mut hasher = RevisionHasher::new();
+        hasher.write(&self.octets());
+        hasher.into_revision()
+    }
+}
 
-        // Hash the length first
-        hasher.write_usize(self.len());
-
-        // Hash the individual items
-        for item in self {
-            hasher.write_revision(item.get_revision());
+/// IpAddr is tagged by whether it's a v4 or v6 address, so that a v4
+/// address and the v4-mapped v6 address (e.g. 1.2.3.4 and ::ffff:1.2.3.4)
+/// differ, just as they do in comparisons
+impl Revisable for IpAddr {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        match self {
+            IpAddr::V4(addr) => {
+                hasher.write_u8(0x4);
+                hasher.write_revisable(addr);
+            }
+            IpAddr::V6(addr) => {
+                hasher.write_u8(0x6);
+                hasher.write_revisable(addr);
+            }
         }
-
-        RevisionHash::new(hasher.finish())
+        hasher.into_revision()
     }
 }
 
-/// Vec<T> where T is Revisable is also Revisable
-impl<T> Revisable for Vec<T>
-where
+/// SocketAddrV4 is hashed as its address followed by its port
+impl Revisable for SocketAddrV4 {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_revisable(self.ip());
+        hasher.write_u16(self.port());
+        hasher.into_revision()
+    }
+}
+
+/// SocketAddrV6 is hashed as its address, port, flow info and scope id
+impl Revisable for SocketAddrV6 {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_revisable(self.ip());
+        hasher.write_u16(self.port());
+        hasher.write_u32(self.flowinfo());
+        hasher.write_u32(self.scope_id());
+        hasher.into_revision()
+    }
+}
+
+/// SocketAddr is tagged by whether it's a v4 or v6 address, like IpAddr
+impl Revisable for SocketAddr {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        match self {
+            SocketAddr::V4(addr) => {
+                hasher.write_u8(0x4);
+                hasher.write_revisable(addr);
+            }
+            SocketAddr::V6(addr) => {
+                hasher.write_u8(0x6);
+                hasher.write_revisable(addr);
+            }
+        }
+        hasher.into_revision()
+    }
+}
+
+/// Path is hashed as the length of its encoded OS string followed by its
+/// bytes, which keeps non-UTF-8 paths distinct as well
+#[cfg(feature = "std")]
+impl Revisable for Path {
+    fn get_revision(&self) -> RevisionHash {
+        let bytes = self.as_os_str().as_encoded_bytes();
+        let mut hasher = RevisionHasher::new();
+        hasher.write_usize(bytes.len());
+        hasher.write(bytes);
+        hasher.into_revision()
+    }
+}
+
+/// PathBuf has the same revision as its contents as a Path
+#[cfg(feature = "std")]
+impl Revisable for PathBuf {
+    fn get_revision(&self) -> RevisionHash {
+        self.as_path().get_revision()
+    }
+}
+
+/// Helper macro for implementing Revisable for range types, which hash a
+/// tag for the kind of range followed by the revisions of their bounds,
+/// so that e.g. 0..5 and 0..=5 differ
+macro_rules! range_impl {
+    ($typename: ident, $tag: expr, $($bound: ident),*) => {
+        impl<T: Revisable> Revisable for $typename<T> {
+            fn get_revision(&self) -> RevisionHash {
+                let mut hasher = RevisionHasher::new();
+                hasher.write_u8($tag);
+                $(hasher.write_revision(self.$bound.get_revision());)*
+                hasher.into_revision()
+            }
+        }
+    };
+}
+
+range_impl!(Range, 0x0, start, end);
+range_impl!(RangeFrom, 0x2, start);
+range_impl!(RangeTo, 0x3, end);
+range_impl!(RangeToInclusive, 0x4, end);
+
+/// RangeInclusive is hashed like the other ranges, but its bounds are
+/// only accessible through methods
+impl<T: Revisable> Revisable for RangeInclusive<T> {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_u8(0x1);
+        hasher.write_revision(self.start().get_revision());
+        hasher.write_revision(self.end().get_revision());
+        hasher.into_revision()
+    }
+}
+
+/// Blanket implementation for Cow. Borrowed and owned variants of the
+/// same content have the same revision, since both are hashed through
+/// the borrowed form.
+#[cfg(feature = "alloc")]
+impl<B> Revisable for Cow<'_, B>
+where
+    B: Revisable + ToOwned + ?Sized,
+{
+    fn get_revision(&self) -> RevisionHash {
+        B::get_revision(self)
+    }
+}
+
+/// Blanket implementation for 1-tuples
+impl<T> Revisable for (T,)
+where
+    T: Revisable,
+{
+    fn get_revision(&self) -> RevisionHash {
+        self.0.get_revision()
+    }
+}
+
+/// Helper macro for implementing Revisable for tuples of Revisable types,
+/// which hash the revision of each element in order
+macro_rules! tuple_impl {
+    ($($param: ident $index: tt),+) => {
+        impl<$($param),+> Revisable for ($($param,)+)
+        where
+            $($param: Revisable,)+
+        {
+            fn get_revision(&self) -> RevisionHash {
+                let mut hasher = RevisionHasher::new();
+                $(hasher.write_revision(self.$index.get_revision());)+
+                hasher.into_revision()
+            }
+        }
+    };
+}
+
+tuple_impl!(T0 0, T1 1);
+tuple_impl!(T0 0, T1 1, T2 2);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11);
+
+/// Revised is a wrapper struct for efficiently tracking the RevisionHash of
+/// a desired type T. The RevisionHash is computed lazily and is only
+/// recomputed when the object has been accessed mutably. This is achieved
+/// transparently using the Deref and DerefMut traits such that Revised<T>
+/// behaves in code just like a plain old T, except that computing its
+/// RevisionHash is optimized to avoid redundant recursions through all its
+/// contents to compute hash values, at the cost of a little extra storage.
+#[derive(Clone)]
+pub struct Revised<T> {
+    /// The stored object
+    value: T,
+
+    /// The revision hash of the stored object, if it's up to date
+    revision: Cell<Option<RevisionHash>>,
+}
+
+impl<T: Revisable> Revised<T> {
+    /// Construct a new Revised object containing the given object
+    pub fn new(value: T) -> Revised<T> {
+        Revised {
+            value,
+            revision: Cell::new(None),
+        }
+    }
+
+    /// Construct a new Revised object containing a different value,
+    /// as when deriving a new version of an existing object. The
+    /// contents and cached revision of self are not used.
+    pub fn with_value(&self, value: T) -> Revised<T> {
+        Revised::new(value)
+    }
+
+    /// Construct a new Revised object containing the given object, whose
+    /// RevisionHash is already known and is cached from the start, as
+    /// when bulk-loading objects whose revisions were stored alongside
+    /// them. The revision must be exactly what value.get_revision() would
+    /// return, which is checked in debug builds.
+    pub fn with_revision(value: T, revision: RevisionHash) -> Revised<T> {
+        debug_assert_eq!(
+            revision,
+            value.get_revision(),
+            "the revision given to Revised::with_revision doesn't match the value"
+        );
+        Revised {
+            value,
+            revision: Cell::new(Some(revision)),
+        }
+    }
+
+    /// Get the contained object's RevisionHash. If the object is
+    /// not mutated, this will compute the RevisionHash only once
+    /// and cache it for reuse.
+    ///
+    /// With the `debug-purity-checks` feature enabled, debug builds
+    /// compute the RevisionHash twice whenever it's not cached and panic
+    /// if the results differ, to catch Revisable implementations which
+    /// aren't pure functions of the object's contents.
+    pub fn get_revision(&self) -> RevisionHash {
+        match self.revision.get() {
+            Some(v) => v,
+            None => {
+                let v = self.value.get_revision();
+                #[cfg(all(feature = "debug-purity-checks", debug_assertions))]
+                assert_eq!(
+                    v,
+                    self.value.get_revision(),
+                    "Revisable implementation is not pure: computing the revision \
+                     of the same unchanged object twice gave different results"
+                );
+                self.revision.set(Some(v));
+                v
+            }
+        }
+    }
+
+    /// Get the cached RevisionHash, if any, without computing it. This
+    /// returns None if the object has been accessed mutably since its
+    /// revision was last computed.
+    pub fn peek_revision(&self) -> Option<RevisionHash> {
+        self.revision.get()
+    }
+
+    /// Returns true if the cached RevisionHash is out of date, meaning
+    /// that the next call to get_revision will compute it
+    pub fn is_dirty(&self) -> bool {
+        self.revision.get().is_none()
+    }
+
+    /// Clear the cached RevisionHash without modifying the contained
+    /// object, so that it is recomputed the next time it's requested.
+    pub fn invalidate(&self) {
+        self.revision.set(None);
+    }
+
+    /// Replace the contained object with a new one and return the old
+    /// one. The cached RevisionHash is cleared.
+    pub fn replace(&mut self, value: T) -> T {
+        self.revision.set(None);
+        core::mem::replace(&mut self.value, value)
+    }
+
+    /// Get mutable access to the contained object. This clears the cached
+    /// RevisionHash just like DerefMut does.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.revision.set(None);
+        &mut self.value
+    }
+
+    /// Mutate the contained object with a closure which may make any
+    /// number of edits, then recompute and cache its RevisionHash once.
+    /// Returns the closure's result along with the new RevisionHash.
+    pub fn edit<R, F: FnOnce(&mut T) -> R>(&mut self, f: F) -> (R, RevisionHash) {
+        let result = f(&mut self.value);
+        let revision = self.value.get_revision();
+        self.revision.set(Some(revision));
+        (result, revision)
+    }
+
+    /// Mutate the contained object with a closure and return its result.
+    /// The contents are always rehashed once after the closure runs, even
+    /// if it didn't change anything, and the new RevisionHash is cached.
+    /// Unlike mutating through DerefMut, the cache is thus never left empty
+    /// and later calls to get_revision don't recompute it. A closure which
+    /// leaves the object semantically unchanged leaves the same revision
+    /// cached as before. Use edit to also get the new revision, e.g. to
+    /// compare it with the previous one.
+    pub fn modify<R, F: FnOnce(&mut T) -> R>(&mut self, f: F) -> R {
+        self.edit(f).0
+    }
+
+    /// Get a view of a part of the contained object, such as one of its
+    /// fields, whose RevisionHash is that of the projected part alone.
+    /// The projection borrows self, which therefore can't be mutated while
+    /// the projection exists, and so the projected part's RevisionHash is
+    /// computed at most once per projection no matter how often it's
+    /// queried.
+    ///
+    /// To also avoid rehashing the projected part across projections, use
+    /// project_cached.
+    pub fn project<'a, U, F>(&'a self, f: F) -> ProjectedRevised<'a, U>
+    where
+        U: Revisable + ?Sized,
+        F: FnOnce(&'a T) -> &'a U,
+    {
+        ProjectedRevised {
+            value: f(&self.value),
+            revision: Cell::new(None),
+            shared: None,
+        }
+    }
+
+    /// Get a view of a part of the contained object like project, whose
+    /// RevisionHash is also remembered in the given ProjectionCache along
+    /// with the parent's cached RevisionHash. As long as the parent is
+    /// unchanged and its revision stays cached, later projections with the
+    /// same cache reuse the projected part's RevisionHash without hashing
+    /// it again. While the parent's revision isn't cached, e.g. right after
+    /// it was mutated, the projected part is hashed as with project. The
+    /// cache should only ever be used with the same projection.
+    pub fn project_cached<'a, U, F>(
+        &'a self,
+        cache: &'a ProjectionCache,
+        f: F,
+    ) -> ProjectedRevised<'a, U>
+    where
+        U: Revisable + ?Sized,
+        F: FnOnce(&'a T) -> &'a U,
+    {
+        let parent_revision = self.revision.get();
+        let revision = match (parent_revision, cache.entry.get()) {
+            (Some(parent), Some((cached_parent, projected))) if parent == cached_parent => {
+                Some(projected)
+            }
+            _ => None,
+        };
+        ProjectedRevised {
+            value: f(&self.value),
+            revision: Cell::new(revision),
+            shared: parent_revision.map(|parent| (parent, cache)),
+        }
+    }
+}
+
+impl<T: Revisable + PartialEq> Revised<T> {
+    /// Compare two Revised objects for equality, ruling out hash
+    /// collisions. The (usually cached) revisions are compared first,
+    /// and only if they match are the contained objects compared too.
+    /// This is slower than comparing revisions alone when the objects
+    /// are equal, but never mistakes a collision for equality.
+    pub fn eq_exact(&self, other: &Revised<T>) -> bool {
+        self.get_revision() == other.get_revision() && self.value == other.value
+    }
+}
+
+/// Clear the cached RevisionHash of every element in a slice of Revised
+/// objects, such as after reloading all of their contents at once.
+pub fn invalidate_all<T: Revisable>(slice: &mut [Revised<T>]) {
+    for item in slice {
+        item.invalidate();
+    }
+}
+
+/// ProjectedRevised is a view of part of a Revised<T>, as returned by
+/// Revised::project, which caches the projected part's RevisionHash.
+pub struct ProjectedRevised<'a, U: ?Sized> {
+    /// The projected part
+    value: &'a U,
+
+    /// The revision hash of the projected part, if it's been computed
+    revision: Cell<Option<RevisionHash>>,
+
+    /// The parent's cached revision and the cache in which to remember the
+    /// projected part's revision under it, if any
+    shared: Option<(RevisionHash, &'a ProjectionCache)>,
+}
+
+/// ProjectionCache remembers the RevisionHash of a projected part of a
+/// Revised<T> across projections made with Revised::project_cached, keyed
+/// on the parent's revision.
+#[derive(Default)]
+pub struct ProjectionCache {
+    /// The parent's revision and the projected part's revision, if any
+    entry: Cell<Option<(RevisionHash, RevisionHash)>>,
+}
+
+impl ProjectionCache {
+    /// Construct a new, empty ProjectionCache
+    pub fn new() -> ProjectionCache {
+        ProjectionCache {
+            entry: Cell::new(None),
+        }
+    }
+}
+
+impl<'a, U: Revisable + ?Sized> ProjectedRevised<'a, U> {
+    /// Get the projected part
+    pub fn get(&self) -> &'a U {
+        self.value
+    }
+
+    /// Get the RevisionHash of the projected part, computing it only
+    /// the first time, unless it's available from a ProjectionCache
+    pub fn get_revision(&self) -> RevisionHash {
+        match self.revision.get() {
+            Some(v) => v,
+            None => {
+                let v = self.value.get_revision();
+                self.revision.set(Some(v));
+                if let Some((parent, cache)) = self.shared {
+                    cache.entry.set(Some((parent, v)));
+                }
+                v
+            }
+        }
+    }
+}
+
+/// ProjectedRevised is Revisable using its cached revision
+impl<U: Revisable + ?Sized> Revisable for ProjectedRevised<'_, U> {
+    fn get_revision(&self) -> RevisionHash {
+        ProjectedRevised::get_revision(self)
+    }
+}
+
+/// Revised<T> can deref to &T
+impl<T: Revisable> Deref for Revised<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+/// Revised<T> can deref to &mut T
+impl<T: Revisable> DerefMut for Revised<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.revision.set(None);
+        &mut self.value
+    }
+}
+
+/// Revised<T> can be viewed as &T
+impl<T: Revisable> AsRef<T> for Revised<T> {
+    fn as_ref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Revised<T> can be viewed as &mut T, which invalidates the
+/// cached revision just like DerefMut
+impl<T: Revisable> AsMut<T> for Revised<T> {
+    fn as_mut(&mut self) -> &mut T {
+        self.revision.set(None);
+        &mut self.value
+    }
+}
+
+/// Revised<T> can be borrowed as &T, e.g. to look up its contents in a
+/// map keyed by T. Borrow requires Revised<T> to compare the same as T,
+/// and since Revised<T> is compared by revision, this holds as long as T's
+/// Revisable implementation agrees with its PartialEq (barring hash
+/// collisions). Revised<T> doesn't implement Hash, so it can't disagree
+/// with the Hash of T and can't be used as a map key itself.
+impl<T: Revisable> Borrow<T> for Revised<T> {
+    fn borrow(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Revised<T> can be borrowed as &mut T, which invalidates the
+/// cached revision just like DerefMut
+impl<T: Revisable> BorrowMut<T> for Revised<T> {
+    fn borrow_mut(&mut self) -> &mut T {
+        self.revision.set(None);
+        &mut self.value
+    }
+}
+
+/// Revised<T> is Revisable (obviously?)
+impl<T: Revisable> Revisable for Revised<T> {
+    fn get_revision(&self) -> RevisionHash {
+        Revised::get_revision(self)
+    }
+
+    fn fast_revision(&self) -> RevisionHash {
+        match self.revision.get() {
+            Some(v) => v,
+            None => Revised::get_revision(self),
+        }
+    }
+}
+
+/// Revised<T> objects are compared by their revisions, which are computed
+/// and cached on both sides if needed. This is hash-based equality and
+/// cheap for large objects whose revisions are already cached, but it's
+/// subject to the usual (astronomically unlikely) chance of a hash
+/// collision. Use Revised::eq_exact to also compare the contents.
+impl<T: Revisable> PartialEq for Revised<T> {
+    fn eq(&self, other: &Revised<T>) -> bool {
+        self.get_revision() == other.get_revision()
+    }
+}
+
+impl<T: Revisable> Eq for Revised<T> {}
+
+/// Revised<T> can be debug-printed, showing its cached revision if any.
+/// The revision is never computed just for printing it.
+impl<T: Revisable + fmt::Debug> fmt::Debug for Revised<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Revised")
+            .field("value", &self.value)
+            .field("revision", &self.revision.get())
+            .finish()
+    }
+}
+
+/// Opaque is a wrapper for values which are never mutated after they are
+/// constructed, such as immutable subtrees. Its RevisionHash is computed
+/// eagerly once on construction and is never recomputed. Unlike Revised<T>,
+/// Opaque<T> only offers shared access to its contents.
+#[derive(Clone)]
+pub struct Opaque<T> {
+    /// The stored object
+    value: T,
+
+    /// The revision hash of the stored object
+    revision: RevisionHash,
+}
+
+impl<T: Revisable> Opaque<T> {
+    /// Construct a new Opaque object, computing its RevisionHash immediately
+    pub fn new(value: T) -> Opaque<T> {
+        let revision = value.get_revision();
+        Opaque { value, revision }
+    }
+
+    /// Get the precomputed RevisionHash of the contained object
+    pub fn get_revision(&self) -> RevisionHash {
+        self.revision
+    }
+
+    /// Consume the Opaque object and return the contained object
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/// Opaque<T> can deref to &T but not &mut T
+impl<T: Revisable> Deref for Opaque<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+/// Opaque<T> is Revisable using its precomputed revision
+impl<T: Revisable> Revisable for Opaque<T> {
+    fn get_revision(&self) -> RevisionHash {
+        Opaque::get_revision(self)
+    }
+}
+
+/// Versioned is a wrapper which tags its contents with a schema version V.
+/// Its RevisionHash combines V with the contents' RevisionHash, so bumping
+/// V after changing the meaning or layout of persisted data changes every
+/// revision and cleanly invalidates any caches keyed on old revisions.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+pub struct Versioned<const V: u64, T>(pub T);
+
+impl<const V: u64, T> Versioned<V, T> {
+    /// The schema version
+    pub const VERSION: u64 = V;
+
+    /// Construct a new Versioned object containing the given object
+    pub fn new(value: T) -> Versioned<V, T> {
+        Versioned(value)
+    }
+
+    /// Consume the Versioned object and return the contained object
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Versioned<V, T> can deref to &T
+impl<const V: u64, T> Deref for Versioned<V, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Versioned<V, T> can deref to &mut T
+impl<const V: u64, T> DerefMut for Versioned<V, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Versioned<V, T> hashes its version before its contents
+impl<const V: u64, T: Revisable> Revisable for Versioned<V, T> {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_u64(V);
+        hasher.write_revision(self.0.get_revision());
+        hasher.into_revision()
+    }
+}
+
+/// RevisableClosure pairs a function with the Revisable state that it's
+/// parameterized by, which is passed to the function explicitly on each
+/// call. Its RevisionHash is that of the captured state alone, since the
+/// code of a closure can't be meaningfully hashed. Two RevisableClosures
+/// with equal state are considered the same revision, so the function for
+/// a given type should be fixed, e.g. by being a plain fn or a closure
+/// which doesn't capture anything from its environment.
+#[derive(Clone)]
+pub struct RevisableClosure<C, F> {
+    /// The state passed to the function
+    capture: C,
+
+    /// The function itself
+    function: F,
+}
+
+impl<C: Revisable, F> RevisableClosure<C, F> {
+    /// Construct a new RevisableClosure from some state and a function
+    pub fn new(capture: C, function: F) -> RevisableClosure<C, F> {
+        RevisableClosure { capture, function }
+    }
+
+    /// Get the captured state
+    pub fn capture(&self) -> &C {
+        &self.capture
+    }
+
+    /// Get mutable access to the captured state
+    pub fn capture_mut(&mut self) -> &mut C {
+        &mut self.capture
+    }
+
+    /// Get the function
+    pub fn function(&self) -> &F {
+        &self.function
+    }
+
+    /// Call the function with the captured state and the given argument.
+    /// Use a tuple to pass several arguments, or () to pass none.
+    pub fn call<A, R>(&self, arg: A) -> R
+    where
+        F: Fn(&C, A) -> R,
+    {
+        (self.function)(&self.capture, arg)
+    }
+}
+
+/// RevisableClosure<C, F> hashes only its captured state
+impl<C: Revisable, F> Revisable for RevisableClosure<C, F> {
+    fn get_revision(&self) -> RevisionHash {
+        self.capture.get_revision()
+    }
+}
+
+/// Get the bits of an f64 after collapsing all NaNs into a single NaN
+/// and -0.0 into +0.0, so that semantically identical values hash equal
+pub(crate) fn canonical_f64_bits(x: f64) -> u64 {
+    if x.is_nan() {
+        f64::NAN.to_bits()
+    } else if x == 0.0 {
+        0
+    } else {
+        x.to_bits()
+    }
+}
+
+/// Get the bits of an f32 after collapsing all NaNs into a single NaN
+/// and -0.0 into +0.0, so that semantically identical values hash equal
+pub(crate) fn canonical_f32_bits(x: f32) -> u32 {
+    if x.is_nan() {
+        f32::NAN.to_bits()
+    } else if x == 0.0 {
+        0
+    } else {
+        x.to_bits()
+    }
+}
+
+/// TotalOrdFloat is a wrapper for f32 or f64 whose RevisionHash follows
+/// IEEE 754 total ordering, as used by total_cmp. Every distinct bit
+/// pattern is treated as a distinct value, so +0.0 and -0.0, as well as
+/// NaNs with different signs or payloads, all produce different revisions.
+/// Use this when bitwise-distinct floats must never be conflated, e.g.
+/// for sorting or deduplicating by exact representation.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct TotalOrdFloat<T>(pub T);
+
+impl<T> TotalOrdFloat<T> {
+    /// Construct a new TotalOrdFloat containing the given float
+    pub fn new(value: T) -> TotalOrdFloat<T> {
+        TotalOrdFloat(value)
+    }
+
+    /// Consume the TotalOrdFloat and return the contained float
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// TotalOrdFloat<f32> hashes the total_cmp key of its bits
+impl Revisable for TotalOrdFloat<f32> {
+    fn get_revision(&self) -> RevisionHash {
+        let bits = self.0.to_bits() as i32;
+        let key = bits ^ (((bits >> 31) as u32) >> 1) as i32;
+        let mut hasher = RevisionHasher::new();
+        hasher.write_i32(key);
+        hasher.into_revision()
+    }
+}
+
+/// TotalOrdFloat<f64> hashes the total_cmp key of its bits
+impl Revisable for TotalOrdFloat<f64> {
+    fn get_revision(&self) -> RevisionHash {
+        let bits = self.0.to_bits() as i64;
+        let key = bits ^ (((bits >> 63) as u64) >> 1) as i64;
+        let mut hasher = RevisionHasher::new();
+        hasher.write_i64(key);
+        hasher.into_revision()
+    }
+}
+
+/// HashAsRevision is a wrapper for types which implement Hash, whose
+/// RevisionHash is computed by feeding the contained value to a
+/// RevisionHasher through its Hash implementation. This avoids writing a
+/// Revisable implementation which duplicates an existing Hash one. The
+/// resulting revision is only as deterministic as the Hash implementation:
+/// it must hash everything that's relevant to the value's contents, and
+/// must not depend on e.g. addresses or iteration order that can differ
+/// between equal values, as with HashMap.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+pub struct HashAsRevision<T>(pub T);
+
+impl<T> HashAsRevision<T> {
+    /// Construct a new HashAsRevision containing the given value
+    pub fn new(value: T) -> HashAsRevision<T> {
+        HashAsRevision(value)
+    }
+
+    /// Consume the HashAsRevision and return the contained value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// HashAsRevision<T> hashes the contained value using its Hash
+/// implementation
+impl<T: Hash> Revisable for HashAsRevision<T> {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        self.0.hash(&mut hasher);
+        hasher.into_revision()
+    }
+}
+
+/// TransitionCounter counts transitions of a state machine. Including one
+/// as a field alongside the state and bumping it on every transition makes
+/// each transition change the RevisionHash, even when the machine returns
+/// to a state it has been in before. Leaving it alone keeps the revision.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+pub struct TransitionCounter(u64);
+
+impl TransitionCounter {
+    /// Construct a new TransitionCounter with a count of zero
+    pub fn new() -> TransitionCounter {
+        TransitionCounter(0)
+    }
+
+    /// Record a transition
+    pub fn bump(&mut self) {
+        self.0 = self.0.wrapping_add(1);
+    }
+
+    /// Get the number of recorded transitions
+    pub fn count(&self) -> u64 {
+        self.0
+    }
+}
+
+/// TransitionCounter is Revisable using its count
+impl Revisable for TransitionCounter {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_u64(self.0);
+        hasher.into_revision()
+    }
+}
+
+/// RevisionWatcher detects changes to an object between successive
+/// observations, such as once per frame or tick, by remembering the
+/// RevisionHash it saw last.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct RevisionWatcher {
+    /// The revision seen by the last observation, if any
+    last_seen: Option<RevisionHash>,
+}
+
+impl RevisionWatcher {
+    /// Construct a new RevisionWatcher which hasn't seen anything yet
+    pub fn new() -> RevisionWatcher {
+        RevisionWatcher { last_seen: None }
+    }
+
+    /// Observe the current revision of the given object and return
+    /// whether it differs from the previously observed revision. The
+    /// first observation always counts as a change.
+    pub fn changed_since_last<T: Revisable + ?Sized>(&mut self, value: &T) -> bool {
+        let revision = value.get_revision();
+        let changed = self.last_seen != Some(revision);
+        self.last_seen = Some(revision);
+        changed
+    }
+
+    /// Get the revision seen by the last observation, if any
+    pub fn last_seen(&self) -> Option<RevisionHash> {
+        self.last_seen
+    }
+}
+
+/// [T] where T is Revisable is also Revisable
+impl<T> Revisable for [T]
+where
+    T: Revisable,
+{
+    fn get_revision(&self) -> RevisionHash {
+        sequence_revision(self.iter().map(|item| item.get_revision()))
+    }
+}
+
+/// Helper function for hashing a sequence of items, given the RevisionHash
+/// of each item in order. This is shared by all ordered collections so that
+/// they produce the same revision as [T], which hashes the length first and
+/// then the revision of each item.
+pub(crate) fn sequence_revision<I>(revisions: I) -> RevisionHash
+where
+    I: ExactSizeIterator<Item = RevisionHash>,
+{
+    let mut hasher = RevisionHasher::new();
+
+    // Hash the length first
+    let len = revisions.len();
+    hasher.write_usize(len);
+
+    // Hash the individual items
+    let mut count: usize = 0;
+    for revision in revisions {
+        hasher.write_revision(revision);
+        count += 1;
+    }
+    debug_assert_eq!(count, len, "ExactSizeIterator reported the wrong length");
+
+    hasher.into_revision()
+}
+
+/// Helper struct collecting the revisions of a sequence whose length isn't
+/// known until it ends. Since [T] hashes the length first, the revision of
+/// each item is kept until the sequence is finished, but the items
+/// themselves aren't.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Default)]
+pub(crate) struct SequenceHasher {
+    /// The revisions of the items so far
+    revisions: Vec<RevisionHash>,
+}
+
+#[cfg(feature = "alloc")]
+impl SequenceHasher {
+    /// Create a SequenceHasher for an empty sequence
+    pub(crate) fn new() -> SequenceHasher {
+        SequenceHasher {
+            revisions: Vec::new(),
+        }
+    }
+
+    /// Add the RevisionHash of the next item in the sequence
+    pub(crate) fn push(&mut self, revision: RevisionHash) {
+        self.revisions.push(revision);
+    }
+
+    /// Get the number of items so far
+    pub(crate) fn len(&self) -> usize {
+        self.revisions.len()
+    }
+
+    /// Get the RevisionHash of the sequence so far
+    pub(crate) fn finish(&self) -> RevisionHash {
+        sequence_revision(self.revisions.iter().copied())
+    }
+}
+
+/// RevisionSink computes the RevisionHash of a sequence of items in a
+/// single pass as they're streamed in, without holding on to the items.
+/// Slices are hashed by writing the number of items first, followed by
+/// each item's revision in order, so the count isn't known until the
+/// sequence ends. The sink reconciles this by keeping only the RevisionHash
+/// of each item, which is 8 bytes regardless of the item's size, and
+/// hashing the count and the kept revisions once it's finished. The result
+/// is the same as the RevisionHash of a slice or Vec holding the same
+/// items.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Default)]
+pub struct RevisionSink {
+    hasher: SequenceHasher,
+}
+
+#[cfg(feature = "alloc")]
+impl RevisionSink {
+    /// Construct a new RevisionSink for an empty sequence
+    pub fn new() -> RevisionSink {
+        RevisionSink {
+            hasher: SequenceHasher::new(),
+        }
+    }
+
+    /// Hash the next item in the sequence
+    pub fn push<T: Revisable + ?Sized>(&mut self, item: &T) {
+        self.hasher.push(item.get_revision());
+    }
+
+    /// Get the number of items pushed so far
+    pub fn len(&self) -> usize {
+        self.hasher.len()
+    }
+
+    /// Returns true if no items have been pushed yet
+    pub fn is_empty(&self) -> bool {
+        self.hasher.len() == 0
+    }
+
+    /// Consume the RevisionSink and return the RevisionHash of the
+    /// sequence of all pushed items
+    pub fn finish(self) -> RevisionHash {
+        self.hasher.finish()
+    }
+}
+
+/// Vec<T> where T is Revisable is also Revisable, and has the same
+/// revision as a slice holding the same items. This is implemented
+/// explicitly rather than relying on deref coercion, so that Vec<T>
+/// satisfies Revisable bounds in generic code.
+#[cfg(feature = "alloc")]
+impl<T> Revisable for Vec<T>
+where
+    T: Revisable,
+{
+    fn get_revision(&self) -> RevisionHash {
+        let s: &[T] = self;
+        s.get_revision()
+    }
+}
+
+/// VecDeque<T> where T is Revisable is also Revisable, and has the same
+/// revision as a slice or Vec holding the same items from front to back
+#[cfg(feature = "alloc")]
+impl<T> Revisable for VecDeque<T>
+where
+    T: Revisable,
+{
+    fn get_revision(&self) -> RevisionHash {
+        sequence_revision(self.iter().map(|item| item.get_revision()))
+    }
+}
+
+/// [T; N] where T is Revisable is also Revisable, and has the same
+/// revision as a slice or Vec holding the same items
+impl<T, const N: usize> Revisable for [T; N]
+where
     T: Revisable,
 {
     fn get_revision(&self) -> RevisionHash {
@@ -302,63 +1878,287 @@ where
     }
 }
 
-/// HashMap<K, T> where K and T are both Revisable is also Revisable
+/// HashMap<K, T> where K and T are both Revisable is also Revisable.
+/// It's hashed without regard to the order of its entries, so the
+/// revision doesn't depend on insertion order, capacity or the map's
+/// hasher.
+#[cfg(feature = "std")]
 impl<K, T> Revisable for HashMap<K, T>
 where
     K: Revisable,
     T: Revisable,
 {
     fn get_revision(&self) -> RevisionHash {
-        // Get an order-independent hash of all items
-        let mut items_hash: u64 = 0;
-        for (key, value) in self {
-            let mut item_hasher = RevisionHasher::new();
-            item_hasher.write_u8(0x1);
-            item_hasher.write_revision(key.get_revision());
-            item_hasher.write_u8(0x2);
-            item_hasher.write_revision(value.get_revision());
-            // Use xor to combine hashes of different items so as
-            // to not depend on the order of items in the hash map
-            items_hash = items_hash.bitxor(item_hasher.finish());
-        }
+        map_revision(self.len(), self.iter())
+    }
+}
 
-        let mut hasher = seahash::SeaHasher::new();
+/// BTreeMap<K, T> where K and T are both Revisable is also Revisable.
+/// It's hashed without regard to the order of its entries just like a
+/// HashMap, so that a BTreeMap and a HashMap with the same entries have
+/// the same revision and the two can be used interchangeably.
+#[cfg(feature = "alloc")]
+impl<K, T> Revisable for BTreeMap<K, T>
+where
+    K: Revisable,
+    T: Revisable,
+{
+    fn get_revision(&self) -> RevisionHash {
+        map_revision(self.len(), self.iter())
+    }
+}
 
-        // Hash the length first
-        hasher.write_usize(self.len());
+/// Helper function for hashing the entries of a map in any order. Like
+/// set_revision, entries are combined using wrapping addition so that
+/// entries with the same hash don't cancel each other out.
+///
+/// Each entry is hashed as a tagged key revision followed by a tagged
+/// value revision. Since revisions are fixed-width, this encoding is
+/// unambiguous and swapping an entry's key and value changes its hash.
+#[cfg(feature = "alloc")]
+fn map_revision<'a, K, T, I>(len: usize, entries: I) -> RevisionHash
+where
+    K: Revisable + 'a,
+    T: Revisable + 'a,
+    I: Iterator<Item = (&'a K, &'a T)>,
+{
+    // Get an order-independent hash of all items
+    let mut items_hash: u64 = 0;
+    for (key, value) in entries {
+        let mut item_hasher = RevisionHasher::new();
+        item_hasher.write_u8(0x1);
+        item_hasher.write_revision(key.get_revision());
+        item_hasher.write_u8(0x2);
+        item_hasher.write_revision(value.get_revision());
+        // Use addition to combine hashes of different items so as
+        // to not depend on the order of items in the map
+        items_hash = items_hash.wrapping_add(item_hasher.finish());
+    }
+
+    let mut hasher = RevisionHasher::new();
+
+    // Hash the length first
+    hasher.write_usize(len);
+
+    // Add the hash value of all items
+    hasher.write_u64(items_hash);
+
+    hasher.into_revision()
+}
+
+/// HashSet<T> where T is Revisable is also Revisable
+#[cfg(feature = "std")]
+impl<T> Revisable for HashSet<T>
+where
+    T: Revisable,
+{
+    fn get_revision(&self) -> RevisionHash {
+        set_revision(self.iter().map(|item| item.get_revision()))
+    }
+}
+
+/// BTreeSet<T> where T is Revisable is also Revisable. Like BTreeMap, it's
+/// hashed without regard to order, and so it has the same revision as a
+/// HashSet with the same items.
+#[cfg(feature = "alloc")]
+impl<T> Revisable for BTreeSet<T>
+where
+    T: Revisable,
+{
+    fn get_revision(&self) -> RevisionHash {
+        set_revision(self.iter().map(|item| item.get_revision()))
+    }
+}
+
+/// Helper function for hashing an unordered set of items, given the
+/// RevisionHash of each item in any order. Items are combined using
+/// wrapping addition, which doesn't depend on their order and, unlike xor,
+/// doesn't let two items with the same hash cancel each other out.
+pub(crate) fn set_revision<I>(revisions: I) -> RevisionHash
+where
+    I: Iterator<Item = RevisionHash>,
+{
+    let mut len: usize = 0;
+    let mut items_hash: u64 = 0;
+    for revision in revisions {
+        len += 1;
+        let mut item_hasher = RevisionHasher::new();
+        item_hasher.write_u8(0x1);
+        item_hasher.write_revision(revision);
+        items_hash = items_hash.wrapping_add(item_hasher.finish());
+    }
+
+    let mut hasher = RevisionHasher::new();
+
+    // Hash the length first
+    hasher.write_usize(len);
+
+    // Add the hash value of all items
+    hasher.write_u64(items_hash);
+
+    hasher.into_revision()
+}
+
+/// Compute a RevisionHash over a collection of distinct items whose order
+/// is meaningful, such as a priority queue of tasks. Items are hashed in
+/// the order given, so reordering them changes the revision even though
+/// the membership is the same. This is unlike the unordered hash of a
+/// HashSet (or a multiset), and gives the same result as hashing the
+/// items as a slice in their current order. This also makes it suitable
+/// for implementing Revisable for custom ordered containers such as ropes
+/// or gap buffers.
+pub fn revision_of_ordered_set<I>(items: I) -> RevisionHash
+where
+    I: IntoIterator,
+    I::IntoIter: ExactSizeIterator,
+    I::Item: Revisable,
+{
+    sequence_revision(items.into_iter().map(|item| item.get_revision()))
+}
+
+/// Compare two snapshots of an object's per-field revisions, given as
+/// pairs of field names and RevisionHashes, and return the names of the
+/// fields in curr which changed or weren't present in prev, in the order
+/// they appear in curr. This lets a replication layer send only the
+/// fields that changed since the last snapshot.
+#[cfg(feature = "alloc")]
+pub fn diff_fields<'a>(
+    prev: &[(&str, RevisionHash)],
+    curr: &[(&'a str, RevisionHash)],
+) -> Vec<&'a str> {
+    curr.iter()
+        .filter(|(name, revision)| {
+            !prev
+                .iter()
+                .any(|(prev_name, prev_revision)| prev_name == name && prev_revision == revision)
+        })
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+/// Compute the RevisionHash of a sequence of items in order, as when
+/// implementing Revisable for a custom ordered container such as a rope
+/// or gap buffer. The result is the same as for a slice holding the same
+/// items, and the same as revision_of_ordered_set, which this forwards to.
+pub fn revise_ordered<I>(items: I) -> RevisionHash
+where
+    I: IntoIterator,
+    I::IntoIter: ExactSizeIterator,
+    I::Item: Revisable,
+{
+    revision_of_ordered_set(items)
+}
 
-        // Add the hash value of all items
-        hasher.write_u64(items_hash);
+/// Compute the RevisionHash of a collection of items without regard to
+/// their order, as when implementing Revisable for a custom unordered
+/// container. The result is the same as for a HashSet or BTreeSet holding
+/// the same items, except that repeated items are all counted, as in a
+/// multiset. For ordered containers, use revise_ordered instead.
+pub fn revise_unordered<I>(items: I) -> RevisionHash
+where
+    I: IntoIterator,
+    I::Item: Revisable,
+{
+    set_revision(items.into_iter().map(|item| item.get_revision()))
+}
+
+/// Compute an order-independent RevisionHash over the contents of a
+/// collection of strings, such as the vocabulary of a string interner.
+/// The result only depends on which strings are present and not on the
+/// order they are given in, so it ignores churn in interned indices.
+pub fn interner_revision<I>(strings: I) -> RevisionHash
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    set_revision(strings.into_iter().map(|s| s.as_ref().get_revision()))
+}
 
-        RevisionHash::new(hasher.finish())
+/// Compute a RevisionHash of a partition of items into equivalence
+/// classes, such as the state of a union-find structure. Each item is
+/// given along with a representative of its class (e.g. its root in the
+/// union-find), and the result depends only on which items are grouped
+/// together, and not on which representatives were chosen or the order
+/// of items or classes. Two union-find states which represent the same
+/// partition thus have equal revisions, regardless of their internal
+/// parent-pointer layout.
+#[cfg(feature = "std")]
+pub fn partition_revision<I, T, R>(items: I) -> RevisionHash
+where
+    I: IntoIterator<Item = (T, R)>,
+    T: Revisable,
+    R: Hash + Eq,
+{
+    let mut classes: HashMap<R, Vec<RevisionHash>> = HashMap::new();
+    for (item, representative) in items {
+        classes
+            .entry(representative)
+            .or_default()
+            .push(item.get_revision());
     }
+    set_revision(
+        classes
+            .into_values()
+            .map(|members| set_revision(members.into_iter())),
+    )
 }
 
-/// RevisedVec<T> is shorthand for Vec<Revised<T>>.
+/// RevisedVec<T> is shorthand for Vec<Revised<T>>. Its RevisionHash
+/// reuses the cached revision of each item, but still combines all of them
+/// whenever it's computed. For large sequences whose aggregate revision is
+/// requested often, such as once per frame, prefer RevisedSlice<T>, which
+/// also caches the aggregate and produces the same revision.
+#[cfg(feature = "alloc")]
 pub type RevisedVec<T> = Vec<Revised<T>>;
 
 /// RevisedHashMap<K, T> is shorthand for HashMap<K, Revised<T>>.
+#[cfg(feature = "std")]
 pub type RevisedHashMap<K, T> = HashMap<K, Revised<T>>;
 
 /// Revised property is the cached result of a function call that is only
 /// evaluated lazily whenever the inputs have changed, according to their
 /// RevisionHash.
-pub struct RevisedProperty<T> {
+///
+/// The second type parameter is only used by refresh1_checked, and is the
+/// type of the arguments it keeps a copy of. It defaults to () for
+/// properties which don't use it.
+pub struct RevisedProperty<T, C = ()> {
     /// The revision of the arguments for the cached value, if present
     revision: Option<RevisionHash>,
 
     /// The cached value
     value: Option<T>,
+
+    /// The revision and a copy of the arguments most recently passed to
+    /// refresh1_checked, which are only kept in debug builds to detect
+    /// revision collisions
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    checked_args: Option<(RevisionHash, C)>,
 }
 
-impl<T> RevisedProperty<T> {
-    /// Create a new RevisedProperty with an empty cache
-    pub fn new() -> RevisedProperty<T> {
+impl<T, C> Default for RevisedProperty<T, C> {
+    fn default() -> RevisedProperty<T, C> {
         RevisedProperty {
             revision: None,
             value: None,
+            checked_args: None,
         }
     }
+}
+
+impl<T> RevisedProperty<T> {
+    /// Create a new RevisedProperty with an empty cache
+    pub fn new() -> RevisedProperty<T> {
+        RevisedProperty::default()
+    }
+}
+
+impl<T, C> RevisedProperty<T, C> {
+    /// Create a new RevisedProperty with an empty cache, for use with
+    /// refresh1_checked on arguments of type C
+    pub fn new_checked() -> RevisedProperty<T, C> {
+        RevisedProperty::default()
+    }
 
     /// Get the cached value, which might not be filled yet.
     /// This stores the result of the refresh* method that
@@ -367,6 +2167,103 @@ impl<T> RevisedProperty<T> {
         self.value.as_ref()
     }
 
+    /// Get the revision of the arguments from which the cached value was
+    /// computed, if any. Since the refreshed function is assumed to be
+    /// pure, this also identifies the cached value itself.
+    pub fn cached_revision(&self) -> Option<RevisionHash> {
+        self.revision
+    }
+
+    /// Forget the revision of the arguments from which the cached value
+    /// was computed, so that the next refresh calls its function even if
+    /// the arguments are unchanged. This is useful when the function
+    /// depends on external state which isn't among its arguments. The
+    /// cached value itself is kept and is still returned by get_cached
+    /// until the next refresh.
+    pub fn invalidate(&mut self) {
+        self.revision = None;
+    }
+
+    /// Update the cache to store the result of calling f on the cached
+    /// output of an upstream PropertyNode, such as another RevisedProperty.
+    /// The upstream's cached revision is used as the cache key, so an
+    /// upstream which is shared by several downstream properties only
+    /// needs to be refreshed once, and each downstream is only recomputed
+    /// if the upstream actually changed since the downstream's last
+    /// refresh. To key on the upstream's output instead, so that the
+    /// downstream isn't recomputed when the upstream was recomputed to the
+    /// same output, refresh from upstream.by_output().
+    ///
+    /// If the upstream has no cached revision, i.e. it was never refreshed
+    /// or it was invalidated since, there's nothing up to date to derive
+    /// from, and self is left untouched, keeping whatever it cached before.
+    pub fn refresh_from<N, F>(&mut self, f: F, upstream: &N)
+    where
+        N: PropertyNode,
+        F: Fn(&N::Output) -> T,
+    {
+        let (Some(current_revision), Some(output)) =
+            (upstream.node_revision(), upstream.node_output())
+        else {
+            return;
+        };
+        if self.revision != Some(current_revision) {
+            self.value = Some(f(output));
+            self.revision = Some(current_revision);
+        }
+    }
+
+    /// Update the cache to store the result of calling f(arg0) as in
+    /// refresh1, where the argument is borrowed rather than moved, so
+    /// that the caller keeps ownership of it for later calls.
+    pub fn refresh1_ref<F, A0>(&mut self, f: F, arg0: &A0)
+    where
+        F: Fn(&A0) -> T,
+        A0: Revisable + ?Sized,
+    {
+        self.refresh1(f, arg0);
+    }
+
+    /// Update the cache to store the result of calling f(arg0, arg1) as
+    /// in refresh2, where the arguments are borrowed rather than moved
+    pub fn refresh2_ref<F, A0, A1>(&mut self, f: F, arg0: &A0, arg1: &A1)
+    where
+        F: Fn(&A0, &A1) -> T,
+        A0: Revisable + ?Sized,
+        A1: Revisable + ?Sized,
+    {
+        self.refresh2(f, arg0, arg1);
+    }
+
+    /// Update the cache to store the result of calling f(arg0, arg1, arg2)
+    /// as in refresh3, where the arguments are borrowed rather than moved
+    pub fn refresh3_ref<F, A0, A1, A2>(&mut self, f: F, arg0: &A0, arg1: &A1, arg2: &A2)
+    where
+        F: Fn(&A0, &A1, &A2) -> T,
+        A0: Revisable + ?Sized,
+        A1: Revisable + ?Sized,
+        A2: Revisable + ?Sized,
+    {
+        self.refresh3(f, arg0, arg1, arg2);
+    }
+
+    /// Update the cache to store the result of calling f(&args), where
+    /// args is any Revisable value such as a tuple of the arguments, which
+    /// f can destructure. This supports any number of arguments that tuples
+    /// are Revisable for, and otherwise behaves just like refresh1 etc.
+    /// f is assumed to be a pure function.
+    pub fn refresh<F, A>(&mut self, f: F, args: A)
+    where
+        F: FnOnce(&A) -> T,
+        A: Revisable,
+    {
+        let current_revision = args.get_revision();
+        if self.revision != Some(current_revision) {
+            self.value = Some(f(&args));
+            self.revision = Some(current_revision);
+        }
+    }
+
     /// Update the cache to store the result of calling f(arg0).
     /// If the function's output from the same arguments is already
     /// cached, the function is not called and the cache is kept.
@@ -421,6 +2318,93 @@ impl<T> RevisedProperty<T> {
         }
     }
 
+    /// Update the cache to store the result of awaiting f(arg0), like
+    /// refresh1 but for asynchronous functions. The future returned by f
+    /// is only created and awaited if the arguments changed. If the
+    /// returned future is dropped before it completes, the cache is left
+    /// as it was. f is assumed to be a pure function.
+    pub async fn refresh1_async<F, Fut, A0>(&mut self, f: F, arg0: A0)
+    where
+        F: FnOnce(A0) -> Fut,
+        Fut: Future<Output = T>,
+        A0: Revisable,
+    {
+        let current_revision = arg0.get_revision();
+        if self.revision != Some(current_revision) {
+            self.value = Some(f(arg0).await);
+            self.revision = Some(current_revision);
+        }
+    }
+
+    /// Update the cache to store the result of calling f(arg0) just like
+    /// refresh1, but in debug builds, also keep a copy of the arguments
+    /// and check that the arguments are actually equal whenever their
+    /// revision matches the cached one. Panics if they differ, which means
+    /// that their revisions collided and the cached value would otherwise
+    /// be stale. In release builds, this behaves exactly like refresh1.
+    /// The property must be created with new_checked for arguments of
+    /// type C.
+    pub fn refresh1_checked<F>(&mut self, f: F, arg0: C)
+    where
+        F: Fn(C) -> T,
+        C: Revisable + Clone + PartialEq,
+    {
+        let current_revision = arg0.get_revision();
+        if self.revision != Some(current_revision) {
+            #[cfg(debug_assertions)]
+            {
+                self.checked_args = Some((current_revision, arg0.clone()));
+            }
+            self.value = Some(f(arg0));
+            self.revision = Some(current_revision);
+        } else {
+            #[cfg(debug_assertions)]
+            if let Some((revision, previous)) = &self.checked_args {
+                assert!(
+                    *revision != current_revision || *previous == arg0,
+                    "RevisedProperty argument revisions collided: \
+                     different arguments have the same revision"
+                );
+            }
+        }
+    }
+
+    /// Refresh the cache as in refresh1 and return the cached value,
+    /// which is guaranteed to be present afterwards
+    pub fn get_or_refresh1<F, A0>(&mut self, f: F, arg0: A0) -> &T
+    where
+        F: Fn(A0) -> T,
+        A0: Revisable,
+    {
+        self.refresh1(f, arg0);
+        self.value.as_ref().unwrap()
+    }
+
+    /// Refresh the cache as in refresh2 and return the cached value,
+    /// which is guaranteed to be present afterwards
+    pub fn get_or_refresh2<F, A0, A1>(&mut self, f: F, arg0: A0, arg1: A1) -> &T
+    where
+        F: Fn(A0, A1) -> T,
+        A0: Revisable,
+        A1: Revisable,
+    {
+        self.refresh2(f, arg0, arg1);
+        self.value.as_ref().unwrap()
+    }
+
+    /// Refresh the cache as in refresh3 and return the cached value,
+    /// which is guaranteed to be present afterwards
+    pub fn get_or_refresh3<F, A0, A1, A2>(&mut self, f: F, arg0: A0, arg1: A1, arg2: A2) -> &T
+    where
+        F: Fn(A0, A1, A2) -> T,
+        A0: Revisable,
+        A1: Revisable,
+        A2: Revisable,
+    {
+        self.refresh3(f, arg0, arg1, arg2);
+        self.value.as_ref().unwrap()
+    }
+
     /// Update the cache to store the result of calling f(arg0, arg1, arg2, arg3).
     /// If the function's output from the same arguments is already
     /// cached, the function is not called and the cache is kept.
@@ -468,4 +2452,214 @@ impl<T> RevisedProperty<T> {
             self.revision = Some(current_revision);
         }
     }
+
+    /// Update the cache to store the result of calling f(arg0, arg1, arg2, arg3, arg4, arg5).
+    /// If the function's output from the same arguments is already
+    /// cached, the function is not called and the cache is kept.
+    /// Otherwise, f is called and the cache is written to.
+    /// f is assumed to be a pure function.
+    #[allow(clippy::too_many_arguments)]
+    pub fn refresh6<F, A0, A1, A2, A3, A4, A5>(
+        &mut self,
+        f: F,
+        arg0: A0,
+        arg1: A1,
+        arg2: A2,
+        arg3: A3,
+        arg4: A4,
+        arg5: A5,
+    ) where
+        F: Fn(A0, A1, A2, A3, A4, A5) -> T,
+        A0: Revisable,
+        A1: Revisable,
+        A2: Revisable,
+        A3: Revisable,
+        A4: Revisable,
+        A5: Revisable,
+    {
+        let current_revision = (&arg0, &arg1, &arg2, &arg3, &arg4, &arg5).get_revision();
+        if self.revision != Some(current_revision) {
+            self.value = Some(f(arg0, arg1, arg2, arg3, arg4, arg5));
+            self.revision = Some(current_revision);
+        }
+    }
+
+    /// Update the cache to store the result of calling f(arg0, arg1, arg2, arg3, arg4, arg5, arg6).
+    /// If the function's output from the same arguments is already
+    /// cached, the function is not called and the cache is kept.
+    /// Otherwise, f is called and the cache is written to.
+    /// f is assumed to be a pure function.
+    #[allow(clippy::too_many_arguments)]
+    pub fn refresh7<F, A0, A1, A2, A3, A4, A5, A6>(
+        &mut self,
+        f: F,
+        arg0: A0,
+        arg1: A1,
+        arg2: A2,
+        arg3: A3,
+        arg4: A4,
+        arg5: A5,
+        arg6: A6,
+    ) where
+        F: Fn(A0, A1, A2, A3, A4, A5, A6) -> T,
+        A0: Revisable,
+        A1: Revisable,
+        A2: Revisable,
+        A3: Revisable,
+        A4: Revisable,
+        A5: Revisable,
+        A6: Revisable,
+    {
+        let current_revision = (&arg0, &arg1, &arg2, &arg3, &arg4, &arg5, &arg6).get_revision();
+        if self.revision != Some(current_revision) {
+            self.value = Some(f(arg0, arg1, arg2, arg3, arg4, arg5, arg6));
+            self.revision = Some(current_revision);
+        }
+    }
+
+    /// Update the cache to store the result of calling f(arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7).
+    /// If the function's output from the same arguments is already
+    /// cached, the function is not called and the cache is kept.
+    /// Otherwise, f is called and the cache is written to.
+    /// f is assumed to be a pure function.
+    #[allow(clippy::too_many_arguments)]
+    pub fn refresh8<F, A0, A1, A2, A3, A4, A5, A6, A7>(
+        &mut self,
+        f: F,
+        arg0: A0,
+        arg1: A1,
+        arg2: A2,
+        arg3: A3,
+        arg4: A4,
+        arg5: A5,
+        arg6: A6,
+        arg7: A7,
+    ) where
+        F: Fn(A0, A1, A2, A3, A4, A5, A6, A7) -> T,
+        A0: Revisable,
+        A1: Revisable,
+        A2: Revisable,
+        A3: Revisable,
+        A4: Revisable,
+        A5: Revisable,
+        A6: Revisable,
+        A7: Revisable,
+    {
+        let current_revision =
+            (&arg0, &arg1, &arg2, &arg3, &arg4, &arg5, &arg6, &arg7).get_revision();
+        if self.revision != Some(current_revision) {
+            self.value = Some(f(arg0, arg1, arg2, arg3, arg4, arg5, arg6, arg7));
+            self.revision = Some(current_revision);
+        }
+    }
+}
+
+/// PropertyNode is a cached computation whose output can be consumed by
+/// downstream properties through RevisedProperty::refresh_from, so that
+/// chains and fan-outs of derived properties share their upstream results.
+pub trait PropertyNode {
+    /// The type of the cached output
+    type Output;
+
+    /// Get the cached output, if any
+    fn node_output(&self) -> Option<&Self::Output>;
+
+    /// Get a RevisionHash identifying the cached output, if any
+    fn node_revision(&self) -> Option<RevisionHash>;
+
+    /// Get a view of this node which is identified by the revision of its
+    /// cached output instead. Downstream properties refreshed from it are
+    /// then only recomputed when the output actually changed, and not when
+    /// the upstream was recomputed but produced the same output as before.
+    fn by_output(&self) -> ByOutput<'_, Self>
+    where
+        Self: Sized,
+        Self::Output: Revisable,
+    {
+        ByOutput { node: self }
+    }
+}
+
+/// ByOutput is a view of a PropertyNode which is identified by the
+/// revision of its cached output, as returned by PropertyNode::by_output.
+/// The output's revision is computed whenever it's requested.
+pub struct ByOutput<'a, N> {
+    /// The viewed node
+    node: &'a N,
+}
+
+impl<N> PropertyNode for ByOutput<'_, N>
+where
+    N: PropertyNode,
+    N::Output: Revisable,
+{
+    type Output = N::Output;
+
+    fn node_output(&self) -> Option<&N::Output> {
+        self.node.node_output()
+    }
+
+    fn node_revision(&self) -> Option<RevisionHash> {
+        self.node.node_output().map(|output| output.get_revision())
+    }
+}
+
+/// RevisedProperty<T> is a PropertyNode which is identified by the
+/// revision of the arguments of its cached value
+impl<T, C> PropertyNode for RevisedProperty<T, C> {
+    type Output = T;
+
+    fn node_output(&self) -> Option<&T> {
+        self.get_cached()
+    }
+
+    fn node_revision(&self) -> Option<RevisionHash> {
+        self.cached_revision()
+    }
+}
+
+/// Implementation details of the derive macro, which are not public API
+#[doc(hidden)]
+pub mod __private {
+    use crate::{set_revision, Revisable, RevisionHasher};
+    use core::hash::Hasher;
+
+    /// Marker trait used only to give a helpful error message when a field
+    /// of a type deriving Revisable doesn't implement Revisable itself
+    #[diagnostic::on_unimplemented(
+        message = "the field type `{Self}` does not implement `Revisable`",
+        label = "this field is hashed by `#[derive(Revisable)]`",
+        note = "implement `Revisable` for `{Self}`, exclude the field with `#[revise(skip)]`, \
+                or hash it with a function using `#[revise(with = \"path::to::function\")]`"
+    )]
+    pub trait RevisableField: Revisable {}
+
+    impl<T: Revisable + ?Sized> RevisableField for T {}
+
+    /// Write the revision of a field of a type deriving Revisable
+    pub fn write_field<T: RevisableField + ?Sized>(hasher: &mut RevisionHasher, value: &T) {
+        hasher.write_revision(value.get_revision());
+    }
+
+    /// Write the revision of a field marked #[revise(order_independent)],
+    /// which combines the revisions of the field's elements regardless of
+    /// their order, like the items of a set
+    pub fn write_unordered_field<'a, T>(hasher: &mut RevisionHasher, value: &'a T)
+    where
+        T: ?Sized,
+        &'a T: IntoIterator,
+        <&'a T as IntoIterator>::Item: Revisable,
+    {
+        hasher.write_revision(set_revision(
+            value.into_iter().map(|item| item.get_revision()),
+        ));
+    }
+
+    /// Write a field marked #[revise(bytes)] as its length followed by
+    /// its raw bytes, rather than the revision of every byte
+    pub fn write_bytes_field<T: AsRef<[u8]> + ?Sized>(hasher: &mut RevisionHasher, value: &T) {
+        let bytes = value.as_ref();
+        hasher.write_usize(bytes.len());
+        hasher.write(bytes);
+    }
 }
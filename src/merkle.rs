@@ -0,0 +1,172 @@
+use alloc::vec::Vec;
+use core::cell::Cell;
+
+use crate::{sequence_revision, Revisable, RevisionHash};
+
+/// MerkleRevised is a fixed-length sequence of Revisable items arranged
+/// as the leaves of a binary hash tree, whose nodes each cache their
+/// RevisionHash. Besides the revision of the root, the revision of every
+/// intermediate node can be queried, so that two trees can be compared
+/// top-down to find where they diverge while looking at only O(log n)
+/// nodes per differing leaf.
+///
+/// Nodes are addressed by a path of child indices from the root, where 0
+/// is the left child and 1 is the right child. The shape of the tree only
+/// depends on the number of leaves: each level pairs up adjacent nodes of
+/// the level below it until a single root remains, and the path to leaf
+/// i is therefore the binary representation of i. An inner node's
+/// RevisionHash is that of its children's revisions hashed as a slice.
+/// The root revision is thus a function of the flattened leaves alone,
+/// and is the same no matter how the tree's contents came about.
+pub struct MerkleRevised<T> {
+    /// The leaves, in order
+    leaves: Vec<T>,
+
+    /// The cached revision of every node, from the leaves up to the root
+    levels: Vec<Vec<Cell<Option<RevisionHash>>>>,
+}
+
+impl<T: Revisable> MerkleRevised<T> {
+    /// Construct a new MerkleRevised with the given leaves
+    pub fn new(leaves: Vec<T>) -> MerkleRevised<T> {
+        let mut levels = Vec::new();
+        let mut width = leaves.len();
+        loop {
+            levels.push((0..width).map(|_| Cell::new(None)).collect::<Vec<_>>());
+            if width <= 1 {
+                break;
+            }
+            width = width.div_ceil(2);
+        }
+        MerkleRevised { leaves, levels }
+    }
+
+    /// Get the number of leaves
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Returns true if there are no leaves
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Get the leaf at the given index
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.leaves.get(index)
+    }
+
+    /// Get mutable access to the leaf at the given index, which invalidates
+    /// the cached revisions of it and of all its ancestors
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.leaves.len() {
+            return None;
+        }
+        for (depth, level) in self.levels.iter().enumerate() {
+            level[index >> depth].set(None);
+        }
+        self.leaves.get_mut(index)
+    }
+
+    /// Replace the leaf at the given index. Panics if the index is out
+    /// of bounds.
+    pub fn set(&mut self, index: usize, value: T) {
+        *self.get_mut(index).expect("leaf index out of bounds") = value;
+    }
+
+    /// Get the leaves as a slice
+    pub fn as_slice(&self) -> &[T] {
+        &self.leaves
+    }
+
+    /// Get the RevisionHash of the root node
+    pub fn get_revision(&self) -> RevisionHash {
+        if self.leaves.is_empty() {
+            return sequence_revision(core::iter::empty());
+        }
+        self.revision_at(self.levels.len() - 1, 0)
+    }
+
+    /// Get the RevisionHash of the node at the given path of child indices,
+    /// with the empty path being the root. Panics if the path doesn't lead
+    /// to a node in this tree.
+    pub fn node_revision(&self, path: &[usize]) -> RevisionHash {
+        if path.is_empty() {
+            return self.get_revision();
+        }
+        let (height, index) = self.locate(path).expect("invalid node path");
+        self.revision_at(height, index)
+    }
+
+    /// Find the path to the first leaf whose RevisionHash differs from the
+    /// leaf at the same position in another tree, or None if both trees'
+    /// revisions are equal. Only subtrees with differing revisions are
+    /// visited. If the trees have different numbers of leaves and thus
+    /// different shapes, the root is reported as the point of divergence.
+    pub fn first_divergence(&self, other: &MerkleRevised<T>) -> Option<Vec<usize>> {
+        if self.get_revision() == other.get_revision() {
+            return None;
+        }
+        if self.leaves.len() != other.leaves.len() {
+            return Some(Vec::new());
+        }
+        let mut path = Vec::new();
+        let mut height = self.levels.len() - 1;
+        let mut index = 0;
+        while height > 0 {
+            height -= 1;
+            let child = (0..2)
+                .map(|c| index * 2 + c)
+                .filter(|i| *i < self.levels[height].len())
+                .position(|i| self.revision_at(height, i) != other.revision_at(height, i))
+                .expect("an inner node's revision differs but none of its children's do");
+            path.push(child);
+            index = index * 2 + child;
+        }
+        Some(path)
+    }
+
+    /// Find the height above the leaves and index within that level of the
+    /// node at the given path, if any
+    fn locate(&self, path: &[usize]) -> Option<(usize, usize)> {
+        let top = self.levels.len() - 1;
+        if path.len() > top {
+            return None;
+        }
+        let mut index = 0;
+        for child in path {
+            if *child > 1 {
+                return None;
+            }
+            index = index * 2 + child;
+        }
+        let height = top - path.len();
+        (index < self.levels[height].len()).then_some((height, index))
+    }
+
+    /// Get the RevisionHash of a node, computing and caching it and the
+    /// revisions of its descendants as needed
+    fn revision_at(&self, height: usize, index: usize) -> RevisionHash {
+        let cache = &self.levels[height][index];
+        if let Some(revision) = cache.get() {
+            return revision;
+        }
+        let revision = if height == 0 {
+            self.leaves[index].get_revision()
+        } else {
+            let below = self.levels[height - 1].len();
+            sequence_revision(
+                (index * 2..(index * 2 + 2).min(below)).map(|i| self.revision_at(height - 1, i)),
+            )
+        };
+        cache.set(Some(revision));
+        revision
+    }
+}
+
+/// MerkleRevised<T> is Revisable using the revision of its root
+impl<T: Revisable> Revisable for MerkleRevised<T> {
+    fn get_revision(&self) -> RevisionHash {
+        MerkleRevised::get_revision(self)
+    }
+}
@@ -0,0 +1,49 @@
+use std::hash::Hasher;
+
+use nalgebra::{
+    allocator::Allocator, DefaultAllocator, Dim, DimName, Matrix, OPoint, Quaternion, RawStorage,
+};
+
+use crate::{canonical_f32_bits, canonical_f64_bits, Revisable, RevisionHash, RevisionHasher};
+
+/// Helper macro for implementing Revisable for nalgebra types with the
+/// given float component type, which is written using the given function
+/// after normalizing it
+macro_rules! nalgebra_impl {
+    ($float: ident, $canonical_fn: ident, $write_fn: ident) => {
+        /// Matrices and vectors hash their dimensions and then their
+        /// components in column-major order
+        impl<R: Dim, C: Dim, S: RawStorage<$float, R, C>> Revisable for Matrix<$float, R, C, S> {
+            fn get_revision(&self) -> RevisionHash {
+                let mut hasher = RevisionHasher::new();
+                hasher.write_usize(self.nrows());
+                hasher.write_usize(self.ncols());
+                for x in self.iter() {
+                    hasher.$write_fn($canonical_fn(*x));
+                }
+                hasher.into_revision()
+            }
+        }
+
+        /// Points hash the same as their coordinates
+        impl<D> Revisable for OPoint<$float, D>
+        where
+            D: DimName,
+            DefaultAllocator: Allocator<D>,
+        {
+            fn get_revision(&self) -> RevisionHash {
+                self.coords.get_revision()
+            }
+        }
+
+        /// Quaternions hash the same as their coordinates
+        impl Revisable for Quaternion<$float> {
+            fn get_revision(&self) -> RevisionHash {
+                self.coords.get_revision()
+            }
+        }
+    };
+}
+
+nalgebra_impl!(f32, canonical_f32_bits, write_u32);
+nalgebra_impl!(f64, canonical_f64_bits, write_u64);
@@ -0,0 +1,13 @@
+use rayon::prelude::*;
+
+use crate::{sequence_revision, Revisable, RevisionHash};
+
+/// Compute the RevisionHash of a slice by computing the revisions of its
+/// items in parallel on the rayon thread pool. The item revisions are
+/// combined in index order afterwards, so the result is identical to the
+/// slice's get_revision. This pays off for large slices of items which are
+/// expensive to hash.
+pub fn par_revision<T: Revisable + Sync>(items: &[T]) -> RevisionHash {
+    let revisions: Vec<RevisionHash> = items.par_iter().map(|item| item.get_revision()).collect();
+    sequence_revision(revisions.into_iter())
+}
@@ -0,0 +1,78 @@
+use alloc::vec::Vec;
+
+use crate::{Revisable, RevisionHash};
+
+/// RevisedCache memoizes a pure function over many distinct arguments,
+/// unlike RevisedProperty which only remembers the result for the most
+/// recent arguments. Results are stored along with the RevisionHash of the
+/// arguments that produced them, and up to `capacity` results are kept.
+/// When the cache is full, the least recently used result is evicted.
+///
+/// Entries are looked up by comparing the revisions of the arguments,
+/// which are plain integer comparisons, in a scan over all entries. This
+/// is meant for modest capacities.
+pub struct RevisedCache<T> {
+    /// The cached results along with the revisions of their arguments,
+    /// least recently used first
+    entries: Vec<(RevisionHash, T)>,
+
+    /// The maximum number of entries
+    capacity: usize,
+}
+
+impl<T> RevisedCache<T> {
+    /// Construct a new, empty RevisedCache which holds at most `capacity`
+    /// results. Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> RevisedCache<T> {
+        assert!(capacity > 0, "RevisedCache capacity must be nonzero");
+        RevisedCache {
+            entries: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Get the maximum number of cached results
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the number of cached results
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no results are cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove all cached results
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Get the cached result of f(arg0). If there is none, f is called
+    /// and its result is cached, evicting the least recently used result
+    /// if the cache is full. Either way, the result becomes the most
+    /// recently used one. f is assumed to be a pure function.
+    pub fn get_or_insert_with1<F, A0>(&mut self, f: F, arg0: A0) -> &T
+    where
+        F: Fn(A0) -> T,
+        A0: Revisable,
+    {
+        let revision = arg0.get_revision();
+        match self.entries.iter().position(|(r, _)| *r == revision) {
+            Some(i) => {
+                let entry = self.entries.remove(i);
+                self.entries.push(entry);
+            }
+            None => {
+                if self.entries.len() == self.capacity {
+                    self.entries.remove(0);
+                }
+                self.entries.push((revision, f(arg0)));
+            }
+        }
+        &self.entries.last().unwrap().1
+    }
+}
@@ -0,0 +1,91 @@
+use core::{
+    cell::Cell,
+    ops::{Deref, DerefMut},
+};
+
+use alloc::boxed::Box;
+
+use crate::{Revisable, RevisionHash};
+
+/// RevisedNotify is like Revised<T>, except that it calls a callback
+/// whenever the stored object's RevisionHash changes. When the revision
+/// is recomputed after the object was mutably accessed, the callback is
+/// called with the new revision only if it differs from the revision that
+/// was computed before, so mutations which leave the object semantically
+/// unchanged don't trigger it. The first computation of the revision has
+/// nothing to compare to and doesn't trigger it either. Since the revision
+/// is only computed lazily, the callback runs inside get_revision rather
+/// than at the time of the mutation.
+pub struct RevisedNotify<T> {
+    /// The stored object
+    value: T,
+
+    /// The revision hash of the stored object, if it's up to date
+    revision: Cell<Option<RevisionHash>>,
+
+    /// The revision hash which was last computed, if any
+    last_computed: Cell<Option<RevisionHash>>,
+
+    /// The callback which is called with each changed revision
+    callback: Box<dyn Fn(RevisionHash)>,
+}
+
+impl<T: Revisable> RevisedNotify<T> {
+    /// Construct a new RevisedNotify object containing the given object,
+    /// which calls the given callback whenever its revision changes
+    pub fn new<F: Fn(RevisionHash) + 'static>(value: T, callback: F) -> RevisedNotify<T> {
+        RevisedNotify {
+            value,
+            revision: Cell::new(None),
+            last_computed: Cell::new(None),
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Get the contained object's RevisionHash, computing it if it's not
+    /// cached and calling the callback if it changed
+    pub fn get_revision(&self) -> RevisionHash {
+        match self.revision.get() {
+            Some(v) => v,
+            None => {
+                let v = self.value.get_revision();
+                self.revision.set(Some(v));
+                let previous = self.last_computed.replace(Some(v));
+                if previous.is_some_and(|p| p != v) {
+                    (self.callback)(v);
+                }
+                v
+            }
+        }
+    }
+
+    /// Consume the RevisedNotify and return the contained object
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/// RevisedNotify<T> can deref to &T
+impl<T: Revisable> Deref for RevisedNotify<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+/// RevisedNotify<T> can deref to &mut T, which invalidates the cached
+/// revision
+impl<T: Revisable> DerefMut for RevisedNotify<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.revision.set(None);
+        &mut self.value
+    }
+}
+
+/// RevisedNotify<T> is Revisable using its cached revision
+impl<T: Revisable> Revisable for RevisedNotify<T> {
+    fn get_revision(&self) -> RevisionHash {
+        RevisedNotify::get_revision(self)
+    }
+}
@@ -0,0 +1,486 @@
+use core::{cell::Cell, fmt, hash::Hasher, marker::PhantomData, time::Duration};
+
+#[cfg(feature = "alloc")]
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    rc::Rc,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+use crate::{canonical_f32_bits, canonical_f64_bits, le_write_impl, Revised};
+
+/// RevisionHash128 is a 128-bit counterpart of RevisionHash, for uses such
+/// as content-addressed stores with very many entries, where the chance of
+/// a collision among 64-bit revisions isn't negligible. It's computed
+/// through the Revisable128 trait, which parallels Revisable.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct RevisionHash128(u128);
+
+impl RevisionHash128 {
+    /// Create a new RevisionHash128 with the given integer value
+    pub fn new(value: u128) -> RevisionHash128 {
+        RevisionHash128(value)
+    }
+
+    /// Get the integer value of the RevisionHash128
+    pub fn value(&self) -> u128 {
+        self.0
+    }
+}
+
+/// RevisionHash128 is displayed as `0x` followed by 32 lowercase
+/// hexadecimal digits
+impl fmt::Display for RevisionHash128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#034x}", self.0)
+    }
+}
+
+/// RevisionHasher128 is the hasher used to compute 128-bit revision
+/// hashes. It's made of two independently seeded SeaHashers which are fed
+/// the same data, and whose outputs make up the two halves of the result.
+#[derive(Clone)]
+pub struct RevisionHasher128 {
+    /// The hasher producing the low half
+    low: seahash::SeaHasher,
+
+    /// The hasher producing the high half
+    high: seahash::SeaHasher,
+}
+
+impl RevisionHasher128 {
+    /// Construct a new RevisionHasher128
+    pub fn new() -> RevisionHasher128 {
+        RevisionHasher128 {
+            low: seahash::SeaHasher::new(),
+            high: seahash::SeaHasher::with_seeds(
+                0x4f1b_bcdc_bfa5_3e0b,
+                0x9e37_79b9_7f4a_7c15,
+                0xd6e8_feb8_6659_fd93,
+                0x2545_f491_4f6c_dd1d,
+            ),
+        }
+    }
+
+    /// Recursively hash another object and write its resulting
+    /// RevisionHash128
+    pub fn write_revisable<T: Revisable128 + ?Sized>(&mut self, t: &T) {
+        self.write_revision(t.get_revision128());
+    }
+
+    /// Hash the RevisionHash128 of another object
+    pub fn write_revision(&mut self, r: RevisionHash128) {
+        self.write_u128(r.value());
+    }
+
+    /// Consume the RevisionHasher128 and return its final RevisionHash128
+    /// which summarizes the contents it has seen
+    pub fn into_revision(self) -> RevisionHash128 {
+        let low = self.low.finish() as u128;
+        let high = self.high.finish() as u128;
+        RevisionHash128((high << 64) | low)
+    }
+}
+
+impl Default for RevisionHasher128 {
+    fn default() -> RevisionHasher128 {
+        RevisionHasher128::new()
+    }
+}
+
+impl Hasher for RevisionHasher128 {
+    /// Get the low half of the current hash. Use into_revision to get all
+    /// 128 bits.
+    fn finish(&self) -> u64 {
+        self.low.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.low.write(bytes);
+        self.high.write(bytes);
+    }
+
+    le_write_impl!(
+        write_u8: u8 => u8,
+        write_u16: u16 => u16,
+        write_u32: u32 => u32,
+        write_u64: u64 => u64,
+        write_u128: u128 => u128,
+        write_usize: usize => u64,
+        write_i8: i8 => i8,
+        write_i16: i16 => i16,
+        write_i32: i32 => i32,
+        write_i64: i64 => i64,
+        write_i128: i128 => i128,
+        write_isize: isize => i64
+    );
+}
+
+/// Revisable128 is the 128-bit counterpart of Revisable, for types whose
+/// RevisionHash128 can be computed. Its implementations follow the same
+/// rules and use the same framing as those of Revisable, only with
+/// RevisionHasher128 and RevisionHash128.
+///
+/// It's implemented for a subset of the types which implement Revisable:
+/// the integer and float primitives, bool, char, (), str, PhantomData,
+/// Duration, references, Cell, Option, Result, slices, arrays, tuples of
+/// up to 12 elements and Revised, as well as String, Box, Rc, Arc, Cow,
+/// Vec, VecDeque and the BTree collections with the `alloc` feature and
+/// HashMap and HashSet with the `std` feature. Other types, such as those
+/// from the optional integrations, need a hand-written implementation.
+pub trait Revisable128 {
+    /// Compute the 128-bit RevisionHash of the object's contents, which
+    /// should be a pure function just like Revisable::get_revision
+    fn get_revision128(&self) -> RevisionHash128;
+}
+
+/// Helper macro for implementing Revisable128 for primitive types, which
+/// write themselves to the hasher like their Revisable implementations do
+macro_rules! revisable128_impl {
+    ($($typename: ty: $write_fn: ident),*) => {
+        $(
+            impl Revisable128 for $typename {
+                fn get_revision128(&self) -> RevisionHash128 {
+                    let mut hasher = RevisionHasher128::new();
+                    hasher.$write_fn((*self).into());
+                    hasher.into_revision()
+                }
+            }
+        )*
+    };
+}
+
+revisable128_impl!(
+    bool: write_u8,
+    u8: write_u8,
+    u16: write_u16,
+    u32: write_u32,
+    u64: write_u64,
+    u128: write_u128,
+    usize: write_usize,
+    i8: write_i8,
+    i16: write_i16,
+    i32: write_i32,
+    i64: write_i64,
+    i128: write_i128,
+    isize: write_isize,
+    char: write_u32
+);
+
+/// f32 is canonicalized like in its Revisable implementation
+impl Revisable128 for f32 {
+    fn get_revision128(&self) -> RevisionHash128 {
+        let mut hasher = RevisionHasher128::new();
+        hasher.write_u32(canonical_f32_bits(*self));
+        hasher.into_revision()
+    }
+}
+
+/// f64 is canonicalized like in its Revisable implementation
+impl Revisable128 for f64 {
+    fn get_revision128(&self) -> RevisionHash128 {
+        let mut hasher = RevisionHasher128::new();
+        hasher.write_u64(canonical_f64_bits(*self));
+        hasher.into_revision()
+    }
+}
+
+impl Revisable128 for () {
+    fn get_revision128(&self) -> RevisionHash128 {
+        RevisionHasher128::new().into_revision()
+    }
+}
+
+/// str hashes its length followed by its UTF-8 bytes
+impl Revisable128 for str {
+    fn get_revision128(&self) -> RevisionHash128 {
+        let mut hasher = RevisionHasher128::new();
+        hasher.write_usize(self.len());
+        hasher.write(self.as_bytes());
+        hasher.into_revision()
+    }
+}
+
+/// String has the same revision as its contents as a str
+#[cfg(feature = "alloc")]
+impl Revisable128 for String {
+    fn get_revision128(&self) -> RevisionHash128 {
+        self.as_str().get_revision128()
+    }
+}
+
+/// Blanket implementation for references
+impl<T: Revisable128 + ?Sized> Revisable128 for &T {
+    fn get_revision128(&self) -> RevisionHash128 {
+        T::get_revision128(self)
+    }
+}
+
+/// Blanket implementation for Box
+#[cfg(feature = "alloc")]
+impl<T: Revisable128 + ?Sized> Revisable128 for Box<T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        T::get_revision128(self)
+    }
+}
+
+/// PhantomData has the same constant revision as ()
+impl<T: ?Sized> Revisable128 for PhantomData<T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        ().get_revision128()
+    }
+}
+
+/// Duration is hashed as its whole seconds and subsecond nanoseconds
+impl Revisable128 for Duration {
+    fn get_revision128(&self) -> RevisionHash128 {
+        let mut hasher = RevisionHasher128::new();
+        hasher.write_u64(self.as_secs());
+        hasher.write_u32(self.subsec_nanos());
+        hasher.into_revision()
+    }
+}
+
+/// Blanket implementation for Rc, which hashes the shared contents
+#[cfg(feature = "alloc")]
+impl<T: Revisable128 + ?Sized> Revisable128 for Rc<T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        T::get_revision128(self)
+    }
+}
+
+/// Blanket implementation for Arc, which hashes the shared contents
+#[cfg(feature = "alloc")]
+impl<T: Revisable128 + ?Sized> Revisable128 for Arc<T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        T::get_revision128(self)
+    }
+}
+
+/// Cow has the same revision whether it's borrowed or owned
+#[cfg(feature = "alloc")]
+impl<B: Revisable128 + ToOwned + ?Sized> Revisable128 for Cow<'_, B> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        B::get_revision128(self)
+    }
+}
+
+/// Blanket implementation for Cell, which hashes a copy of the current
+/// contents
+impl<T: Revisable128 + Copy> Revisable128 for Cell<T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        self.get().get_revision128()
+    }
+}
+
+/// Revised hashes its contents. Only the 64-bit revision is cached, so
+/// this is recomputed on every call.
+impl<T: Revisable128> Revisable128 for Revised<T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        self.value.get_revision128()
+    }
+}
+
+/// Option is tagged by whether a value is present
+impl<T: Revisable128> Revisable128 for Option<T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        let mut hasher = RevisionHasher128::new();
+        match self {
+            None => hasher.write_u8(0x0),
+            Some(value) => {
+                hasher.write_u8(0x1);
+                hasher.write_revisable(value);
+            }
+        }
+        hasher.into_revision()
+    }
+}
+
+/// Result is tagged by whether it's Ok or Err
+impl<T: Revisable128, E: Revisable128> Revisable128 for Result<T, E> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        let mut hasher = RevisionHasher128::new();
+        match self {
+            Ok(value) => {
+                hasher.write_u8(0x0);
+                hasher.write_revisable(value);
+            }
+            Err(error) => {
+                hasher.write_u8(0x1);
+                hasher.write_revisable(error);
+            }
+        }
+        hasher.into_revision()
+    }
+}
+
+/// Helper function for hashing a sequence of items in order, using the
+/// same framing as sequence_revision
+fn sequence_revision128<I>(revisions: I) -> RevisionHash128
+where
+    I: ExactSizeIterator<Item = RevisionHash128>,
+{
+    let mut hasher = RevisionHasher128::new();
+
+    // Hash the length first
+    hasher.write_usize(revisions.len());
+
+    for revision in revisions {
+        hasher.write_revision(revision);
+    }
+
+    hasher.into_revision()
+}
+
+/// Helper function for hashing a set of items in any order, using the
+/// same tagged wrapping addition as set_revision so that items with the
+/// same hash don't cancel each other out
+#[cfg(feature = "alloc")]
+fn set_revision128<I>(revisions: I) -> RevisionHash128
+where
+    I: Iterator<Item = RevisionHash128>,
+{
+    let mut len: usize = 0;
+    let mut items_hash: u128 = 0;
+    for revision in revisions {
+        len += 1;
+        let mut item_hasher = RevisionHasher128::new();
+        item_hasher.write_u8(0x1);
+        item_hasher.write_revision(revision);
+        items_hash = items_hash.wrapping_add(item_hasher.into_revision().value());
+    }
+
+    let mut hasher = RevisionHasher128::new();
+    hasher.write_usize(len);
+    hasher.write_u128(items_hash);
+    hasher.into_revision()
+}
+
+/// Helper function for hashing the entries of a map in any order, using
+/// the same tags and wrapping addition as map_revision
+#[cfg(feature = "alloc")]
+fn map_revision128<'a, K, T, I>(len: usize, entries: I) -> RevisionHash128
+where
+    K: Revisable128 + 'a,
+    T: Revisable128 + 'a,
+    I: Iterator<Item = (&'a K, &'a T)>,
+{
+    let mut items_hash: u128 = 0;
+    for (key, value) in entries {
+        let mut item_hasher = RevisionHasher128::new();
+        item_hasher.write_u8(0x1);
+        item_hasher.write_revisable(key);
+        item_hasher.write_u8(0x2);
+        item_hasher.write_revisable(value);
+        items_hash = items_hash.wrapping_add(item_hasher.into_revision().value());
+    }
+
+    let mut hasher = RevisionHasher128::new();
+    hasher.write_usize(len);
+    hasher.write_u128(items_hash);
+    hasher.into_revision()
+}
+
+/// [T] hashes its length, followed by the revision of each item in order
+impl<T: Revisable128> Revisable128 for [T] {
+    fn get_revision128(&self) -> RevisionHash128 {
+        sequence_revision128(self.iter().map(|item| item.get_revision128()))
+    }
+}
+
+/// [T; N] has the same revision as a slice holding the same items
+impl<T: Revisable128, const N: usize> Revisable128 for [T; N] {
+    fn get_revision128(&self) -> RevisionHash128 {
+        self.as_slice().get_revision128()
+    }
+}
+
+/// Vec<T> has the same revision as a slice holding the same items
+#[cfg(feature = "alloc")]
+impl<T: Revisable128> Revisable128 for Vec<T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        self.as_slice().get_revision128()
+    }
+}
+
+/// VecDeque<T> has the same revision as a slice holding the same items
+#[cfg(feature = "alloc")]
+impl<T: Revisable128> Revisable128 for VecDeque<T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        sequence_revision128(self.iter().map(|item| item.get_revision128()))
+    }
+}
+
+/// HashMap is hashed without regard to the order of its entries
+#[cfg(feature = "std")]
+impl<K: Revisable128, T: Revisable128> Revisable128 for HashMap<K, T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        map_revision128(self.len(), self.iter())
+    }
+}
+
+/// BTreeMap has the same revision as a HashMap with the same entries
+#[cfg(feature = "alloc")]
+impl<K: Revisable128, T: Revisable128> Revisable128 for BTreeMap<K, T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        map_revision128(self.len(), self.iter())
+    }
+}
+
+/// HashSet is hashed without regard to the order of its items
+#[cfg(feature = "std")]
+impl<T: Revisable128> Revisable128 for HashSet<T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        set_revision128(self.iter().map(|item| item.get_revision128()))
+    }
+}
+
+/// BTreeSet has the same revision as a HashSet with the same items
+#[cfg(feature = "alloc")]
+impl<T: Revisable128> Revisable128 for BTreeSet<T> {
+    fn get_revision128(&self) -> RevisionHash128 {
+        set_revision128(self.iter().map(|item| item.get_revision128()))
+    }
+}
+
+/// 1-tuples have the same revision as their contents
+impl<T: Revisable128> Revisable128 for (T,) {
+    fn get_revision128(&self) -> RevisionHash128 {
+        self.0.get_revision128()
+    }
+}
+
+/// Helper macro for implementing Revisable128 for tuples, which hash the
+/// revision of each element in order
+macro_rules! tuple128_impl {
+    ($($param: ident $index: tt),+) => {
+        impl<$($param),+> Revisable128 for ($($param,)+)
+        where
+            $($param: Revisable128,)+
+        {
+            fn get_revision128(&self) -> RevisionHash128 {
+                let mut hasher = RevisionHasher128::new();
+                $(hasher.write_revisable(&self.$index);)+
+                hasher.into_revision()
+            }
+        }
+    };
+}
+
+tuple128_impl!(T0 0, T1 1);
+tuple128_impl!(T0 0, T1 1, T2 2);
+tuple128_impl!(T0 0, T1 1, T2 2, T3 3);
+tuple128_impl!(T0 0, T1 1, T2 2, T3 3, T4 4);
+tuple128_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+tuple128_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+tuple128_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
+tuple128_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8);
+tuple128_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9);
+tuple128_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10);
+tuple128_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11);
@@ -0,0 +1,66 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{Revisable, RevisionHash};
+
+/// RevisionCache memoizes the RevisionHash of values shared through Rc,
+/// keyed by the identity of their allocation, so that a subtree which is
+/// referenced many times in a DAG is only hashed once while computing the
+/// revision of the whole structure.
+///
+/// Since entries are keyed by address, a RevisionCache is meant to be used
+/// for a single pass over a structure which isn't modified for the
+/// duration. If an Rc is dropped, its address may be reused by another
+/// allocation, which would then be mistaken for the original.
+#[derive(Default)]
+pub struct RevisionCache {
+    /// The revisions of visited allocations, keyed by address
+    revisions: HashMap<*const (), RevisionHash>,
+}
+
+impl RevisionCache {
+    /// Create a new, empty RevisionCache
+    pub fn new() -> RevisionCache {
+        RevisionCache {
+            revisions: HashMap::new(),
+        }
+    }
+
+    /// Get the RevisionHash of the value behind the given Rc. If the same
+    /// allocation has been seen before, its cached revision is returned.
+    /// Otherwise, f is called with the cache itself, so that it can look up
+    /// shared children recursively, and its result is cached.
+    pub fn get_or_insert_with<T, F>(&mut self, rc: &Rc<T>, f: F) -> RevisionHash
+    where
+        T: ?Sized,
+        F: FnOnce(&mut RevisionCache) -> RevisionHash,
+    {
+        let key = Rc::as_ptr(rc) as *const ();
+        if let Some(revision) = self.revisions.get(&key) {
+            return *revision;
+        }
+        let revision = f(self);
+        self.revisions.insert(key, revision);
+        revision
+    }
+
+    /// Get the RevisionHash of the value behind the given Rc, using its
+    /// Revisable implementation the first time the allocation is seen.
+    pub fn get_revision<T: Revisable + ?Sized>(&mut self, rc: &Rc<T>) -> RevisionHash {
+        self.get_or_insert_with(rc, |_| rc.get_revision())
+    }
+
+    /// Get the number of cached allocations
+    pub fn len(&self) -> usize {
+        self.revisions.len()
+    }
+
+    /// Returns true if no allocations are cached
+    pub fn is_empty(&self) -> bool {
+        self.revisions.is_empty()
+    }
+
+    /// Remove all cached revisions
+    pub fn clear(&mut self) {
+        self.revisions.clear();
+    }
+}
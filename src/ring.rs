@@ -0,0 +1,126 @@
+use alloc::collections::VecDeque;
+use core::cell::Cell;
+
+use crate::{sequence_revision, Revisable, RevisionHash};
+
+/// RevisedRing is a fixed-capacity ring buffer of Revisable items, such as
+/// a trailing window of samples. Pushing a new item when the ring is full
+/// evicts the oldest item. Its RevisionHash is the same as that of the
+/// current contents hashed front-to-back as a slice.
+///
+/// Each item's RevisionHash is computed only once when it is pushed, so
+/// pushing an item costs a single item hash. The combined revision is
+/// computed lazily from the stored item revisions and cached until the
+/// next push.
+pub struct RevisedRing<T> {
+    /// The stored items, oldest first, along with their revisions
+    items: VecDeque<(T, RevisionHash)>,
+
+    /// The maximum number of items
+    capacity: usize,
+
+    /// The revision hash of the whole window, if it's up to date
+    revision: Cell<Option<RevisionHash>>,
+}
+
+impl<T: Revisable> RevisedRing<T> {
+    /// Construct a new, empty RevisedRing which holds at most `capacity`
+    /// items. Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> RevisedRing<T> {
+        assert!(capacity > 0, "RevisedRing capacity must be nonzero");
+        RevisedRing {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+            revision: Cell::new(None),
+        }
+    }
+
+    /// Add an item to the back of the ring. If the ring was full, the
+    /// oldest item is removed and returned.
+    pub fn push(&mut self, item: T) -> Option<T> {
+        let evicted = if self.items.len() == self.capacity {
+            self.items.pop_front().map(|(item, _)| item)
+        } else {
+            None
+        };
+        let revision = item.get_revision();
+        self.items.push_back((item, revision));
+        self.revision.set(None);
+        evicted
+    }
+
+    /// Remove and return the oldest item, if any
+    pub fn pop_front(&mut self) -> Option<T> {
+        let item = self.items.pop_front().map(|(item, _)| item);
+        if item.is_some() {
+            self.revision.set(None);
+        }
+        item
+    }
+
+    /// Remove all items
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.revision.set(None);
+    }
+
+    /// Get the maximum number of items
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the current number of items
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns true if there are no items
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns true if the next push will evict the oldest item
+    pub fn is_full(&self) -> bool {
+        self.items.len() == self.capacity
+    }
+
+    /// Get the item at the given position, counting from the oldest
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.items.get(index).map(|(item, _)| item)
+    }
+
+    /// Get the oldest item
+    pub fn front(&self) -> Option<&T> {
+        self.items.front().map(|(item, _)| item)
+    }
+
+    /// Get the newest item
+    pub fn back(&self) -> Option<&T> {
+        self.items.back().map(|(item, _)| item)
+    }
+
+    /// Iterate over the items from oldest to newest
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.items.iter().map(|(item, _)| item)
+    }
+
+    /// Get the RevisionHash of the current window. This is cached until
+    /// the contents change.
+    pub fn get_revision(&self) -> RevisionHash {
+        match self.revision.get() {
+            Some(v) => v,
+            None => {
+                let v = sequence_revision(self.items.iter().map(|(_, r)| *r));
+                self.revision.set(Some(v));
+                v
+            }
+        }
+    }
+}
+
+/// RevisedRing<T> is Revisable using its cached revision
+impl<T: Revisable> Revisable for RevisedRing<T> {
+    fn get_revision(&self) -> RevisionHash {
+        RevisedRing::get_revision(self)
+    }
+}
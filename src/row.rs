@@ -0,0 +1,128 @@
+use alloc::vec::Vec;
+use core::hash::Hasher;
+
+use crate::{canonical_f64_bits, set_revision, RevisionHash, RevisionHasher, SequenceHasher};
+
+// Type tags distinguishing the column types of a RowRevisionBuilder, so
+// that e.g. a NULL integer and a NULL text column hash differently
+const TAG_BOOL: u8 = 0x1;
+const TAG_I64: u8 = 0x2;
+const TAG_F64: u8 = 0x3;
+const TAG_TEXT: u8 = 0x4;
+const TAG_BLOB: u8 = 0x5;
+
+/// RowRevisionBuilder computes the RevisionHash of a row from an external
+/// source such as a database, one typed column at a time. Each column
+/// hashes a tag for its type, a tag for whether it's NULL (None), and its
+/// value if present, so that a NULL never collides with an empty string
+/// or a zero.
+///
+/// Columns are positional by default, in which case the row's revision
+/// depends on the order in which they were added. Calling name before
+/// adding a column makes it a named column instead, and named columns
+/// are combined without regard to their order, so that rows can be
+/// hashed consistently even if their columns are retrieved in a
+/// different order.
+#[derive(Clone, Default)]
+pub struct RowRevisionBuilder {
+    /// The positional columns so far
+    positional: SequenceHasher,
+
+    /// The revisions of the named columns so far, in any order
+    named: Vec<RevisionHash>,
+
+    /// The name given to the next column, if any
+    name: Option<RevisionHash>,
+}
+
+impl RowRevisionBuilder {
+    /// Construct a new RowRevisionBuilder for a row with no columns
+    pub fn new() -> RowRevisionBuilder {
+        RowRevisionBuilder {
+            positional: SequenceHasher::new(),
+            named: Vec::new(),
+            name: None,
+        }
+    }
+
+    /// Give a name to the next column to be added, making it a named
+    /// column whose position doesn't matter
+    pub fn name(&mut self, name: &str) -> &mut Self {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_usize(name.len());
+        hasher.write(name.as_bytes());
+        self.name = Some(hasher.into_revision());
+        self
+    }
+
+    /// Add a nullable boolean column
+    pub fn add_bool(&mut self, value: Option<bool>) -> &mut Self {
+        self.add_column(TAG_BOOL, value, |hasher, x| hasher.write_u8(x as u8))
+    }
+
+    /// Add a nullable integer column
+    pub fn add_i64(&mut self, value: Option<i64>) -> &mut Self {
+        self.add_column(TAG_I64, value, |hasher, x| hasher.write_i64(x))
+    }
+
+    /// Add a nullable floating point column. All NaNs are treated as
+    /// equal, and so are +0.0 and -0.0.
+    pub fn add_f64(&mut self, value: Option<f64>) -> &mut Self {
+        self.add_column(TAG_F64, value, |hasher, x| {
+            hasher.write_u64(canonical_f64_bits(x))
+        })
+    }
+
+    /// Add a nullable text column
+    pub fn add_text(&mut self, value: Option<&str>) -> &mut Self {
+        self.add_column(TAG_TEXT, value, |hasher, x| {
+            hasher.write_usize(x.len());
+            hasher.write(x.as_bytes());
+        })
+    }
+
+    /// Add a nullable binary column
+    pub fn add_blob(&mut self, value: Option<&[u8]>) -> &mut Self {
+        self.add_column(TAG_BLOB, value, |hasher, x| {
+            hasher.write_usize(x.len());
+            hasher.write(x);
+        })
+    }
+
+    /// Get the RevisionHash of the row's columns so far
+    pub fn finish(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_revision(self.positional.finish());
+        hasher.write_revision(set_revision(self.named.iter().copied()));
+        hasher.into_revision()
+    }
+
+    /// Hash a column's type and null tags and its value if present, and
+    /// add it as a named column if a name was given or positionally if not
+    fn add_column<T, F>(&mut self, tag: u8, value: Option<T>, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut RevisionHasher, T),
+    {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_u8(tag);
+        match value {
+            None => hasher.write_u8(0x0),
+            Some(x) => {
+                hasher.write_u8(0x1);
+                f(&mut hasher, x);
+            }
+        }
+        let column = hasher.into_revision();
+
+        match self.name.take() {
+            Some(name) => {
+                let mut hasher = RevisionHasher::new();
+                hasher.write_revision(name);
+                hasher.write_revision(column);
+                self.named.push(hasher.into_revision());
+            }
+            None => self.positional.push(column),
+        }
+        self
+    }
+}
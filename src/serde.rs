@@ -0,0 +1,73 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Revisable, Revised, RevisedProperty, RevisionHash};
+
+/// RevisionHash is serialized as its integer value, so that revisions
+/// can be saved and compared against again after being loaded
+impl Serialize for RevisionHash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RevisionHash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u64::deserialize(deserializer).map(RevisionHash::new)
+    }
+}
+
+/// Revised<T> is serialized as just the stored object, since its cached
+/// revision is derived from it. A deserialized Revised<T> starts out with
+/// an empty cache.
+impl<T: Revisable + Serialize> Serialize for Revised<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, T: Revisable + Deserialize<'de>> Deserialize<'de> for Revised<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Revised::new)
+    }
+}
+
+/// Serialized form of a RevisedProperty
+#[derive(Serialize)]
+#[serde(rename = "RevisedProperty")]
+struct RevisedPropertyRef<'a, T> {
+    revision: Option<u64>,
+    value: Option<&'a T>,
+}
+
+/// Deserialized form of a RevisedProperty
+#[derive(Deserialize)]
+#[serde(rename = "RevisedProperty")]
+struct RevisedPropertyData<T> {
+    revision: Option<u64>,
+    value: Option<T>,
+}
+
+/// RevisedProperty<T> is serialized as both its cached value and the
+/// revision of the arguments that produced it, so that a cache which is
+/// saved and loaded again is still hit on the next refresh if the
+/// arguments haven't changed.
+impl<T: Serialize> Serialize for RevisedProperty<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RevisedPropertyRef {
+            revision: self.revision.map(|r| r.value()),
+            value: self.value.as_ref(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for RevisedProperty<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = RevisedPropertyData::deserialize(deserializer)?;
+        Ok(RevisedProperty {
+            revision: data.revision.map(RevisionHash::new),
+            value: data.value,
+            ..RevisedProperty::new()
+        })
+    }
+}
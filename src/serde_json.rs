@@ -0,0 +1,67 @@
+use std::hash::Hasher;
+
+use serde_json::{Number, Value};
+
+use crate::{
+    canonical_f64_bits, sequence_revision, set_revision, Revisable, RevisionHash, RevisionHasher,
+};
+
+/// Hash a JSON string by its length and UTF-8 bytes
+fn write_str(hasher: &mut RevisionHasher, s: &str) {
+    hasher.write_usize(s.len());
+    hasher.write(s.as_bytes());
+}
+
+/// Hash a JSON number, distinguishing integers from floats since
+/// serde_json does too. Floats are canonicalized.
+fn write_number(hasher: &mut RevisionHasher, n: &Number) {
+    if let Some(x) = n.as_u64() {
+        hasher.write_u8(0x0);
+        hasher.write_u64(x);
+    } else if let Some(x) = n.as_i64() {
+        hasher.write_u8(0x1);
+        hasher.write_i64(x);
+    } else {
+        hasher.write_u8(0x2);
+        hasher.write_u64(canonical_f64_bits(n.as_f64().unwrap_or(f64::NAN)));
+    }
+}
+
+/// JSON values hash a tag for their kind followed by their contents.
+/// Arrays are hashed in order like slices, while objects are hashed
+/// without regard to the order of their keys, since JSON objects are
+/// unordered.
+impl Revisable for Value {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        match self {
+            Value::Null => hasher.write_u8(0x0),
+            Value::Bool(b) => {
+                hasher.write_u8(0x1);
+                hasher.write_u8(*b as u8);
+            }
+            Value::Number(n) => {
+                hasher.write_u8(0x2);
+                write_number(&mut hasher, n);
+            }
+            Value::String(s) => {
+                hasher.write_u8(0x3);
+                write_str(&mut hasher, s);
+            }
+            Value::Array(items) => {
+                hasher.write_u8(0x4);
+                hasher.write_revision(sequence_revision(items.iter().map(|v| v.get_revision())));
+            }
+            Value::Object(entries) => {
+                hasher.write_u8(0x5);
+                hasher.write_revision(set_revision(entries.iter().map(|(k, v)| {
+                    let mut entry_hasher = RevisionHasher::new();
+                    write_str(&mut entry_hasher, k);
+                    entry_hasher.write_revision(v.get_revision());
+                    entry_hasher.into_revision()
+                })));
+            }
+        }
+        hasher.into_revision()
+    }
+}
@@ -0,0 +1,111 @@
+use alloc::rc::Rc;
+use core::{
+    cell::Cell,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{Revisable, RevisionHash};
+
+/// The stored object of a SharedRevised together with its cache, which
+/// are shared between clones as a unit
+struct SharedInner<T> {
+    value: T,
+    revision: Cell<Option<RevisionHash>>,
+}
+
+/// SharedRevised is like Revised<T>, except that clones share both the
+/// stored object and its cached revision through an Rc. Computing the
+/// revision through one clone makes it available to all of its unchanged
+/// siblings.
+///
+/// On the first mutable access to a clone that is still shared, the object
+/// is deep-cloned and that clone gets a fresh, empty cache of its own. The
+/// siblings keep the original object and their cached revision stays valid.
+/// Mutating a SharedRevised that is not shared neither clones the object nor
+/// affects any other cache.
+///
+/// The RevisionHash of a SharedRevised<T> is the same as that of a
+/// Revised<T> with the same contents.
+pub struct SharedRevised<T> {
+    inner: Rc<SharedInner<T>>,
+}
+
+impl<T: Revisable> SharedRevised<T> {
+    /// Construct a new SharedRevised object containing the given object
+    pub fn new(value: T) -> SharedRevised<T> {
+        SharedRevised {
+            inner: Rc::new(SharedInner {
+                value,
+                revision: Cell::new(None),
+            }),
+        }
+    }
+
+    /// Get the contained object's RevisionHash. The result is cached and
+    /// shared with all clones that have not been mutated since.
+    pub fn get_revision(&self) -> RevisionHash {
+        match self.inner.revision.get() {
+            Some(v) => v,
+            None => {
+                let v = self.inner.value.get_revision();
+                self.inner.revision.set(Some(v));
+                v
+            }
+        }
+    }
+
+    /// Returns true if both objects share the same stored object and cache
+    pub fn ptr_eq(this: &SharedRevised<T>, other: &SharedRevised<T>) -> bool {
+        Rc::ptr_eq(&this.inner, &other.inner)
+    }
+
+    /// Returns true if the stored object is shared with another clone,
+    /// meaning that the next mutable access will deep-clone it
+    pub fn is_shared(this: &SharedRevised<T>) -> bool {
+        Rc::strong_count(&this.inner) > 1
+    }
+}
+
+/// Cloning a SharedRevised<T> shares the stored object and its cache
+/// rather than cloning the object itself
+impl<T> Clone for SharedRevised<T> {
+    fn clone(&self) -> Self {
+        SharedRevised {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+/// SharedRevised<T> can deref to &T
+impl<T: Revisable> Deref for SharedRevised<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner.value
+    }
+}
+
+/// SharedRevised<T> can deref to &mut T. If the object is shared, it is
+/// deep-cloned into a new allocation with an empty cache first, so that
+/// the siblings' cache is left untouched.
+impl<T: Revisable + Clone> DerefMut for SharedRevised<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if Rc::get_mut(&mut self.inner).is_none() {
+            self.inner = Rc::new(SharedInner {
+                value: self.inner.value.clone(),
+                revision: Cell::new(None),
+            });
+        }
+        // The Rc is now unique, so no other clone can observe this cache
+        let inner = Rc::get_mut(&mut self.inner).unwrap();
+        inner.revision.set(None);
+        &mut inner.value
+    }
+}
+
+/// SharedRevised<T> is Revisable using its (shared) cached revision
+impl<T: Revisable> Revisable for SharedRevised<T> {
+    fn get_revision(&self) -> RevisionHash {
+        SharedRevised::get_revision(self)
+    }
+}
@@ -0,0 +1,119 @@
+use alloc::vec::Vec;
+use core::cell::Cell;
+
+use crate::{sequence_revision, Revisable, Revised, RevisionHash};
+
+/// RevisedSlice is a growable sequence of Revised items, such as the
+/// elements of an editor's document, which caches the revision of each
+/// item as well as that of the whole sequence. Editing a single item only
+/// invalidates that item and the sequence, so that recomputing the
+/// sequence's RevisionHash after an edit rehashes the edited item and then
+/// combines the cached hashes of the others, without revisiting their
+/// contents. Reading the sequence's RevisionHash again without any edits
+/// in between doesn't recompute anything.
+///
+/// The RevisionHash of a RevisedSlice<T> is the same as that of a slice or
+/// Vec holding the same items.
+pub struct RevisedSlice<T> {
+    /// The stored items
+    items: Vec<Revised<T>>,
+
+    /// The revision of the whole sequence, if it's up to date
+    revision: Cell<Option<RevisionHash>>,
+}
+
+impl<T: Revisable> RevisedSlice<T> {
+    /// Construct a new, empty RevisedSlice
+    pub fn new() -> RevisedSlice<T> {
+        RevisedSlice {
+            items: Vec::new(),
+            revision: Cell::new(None),
+        }
+    }
+
+    /// Get the number of items
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns true if there are no items
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Get the item at the given index, if it's in bounds
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.items.get(index).map(|item| &**item)
+    }
+
+    /// Get mutable access to the item at the given index, if it's in
+    /// bounds. This invalidates the cached revisions of the item and of
+    /// the whole sequence.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let item = self.items.get_mut(index)?;
+        self.revision.set(None);
+        Some(&mut **item)
+    }
+
+    /// Replace the item at the given index. Panics if the index is out of
+    /// bounds.
+    pub fn set(&mut self, index: usize, value: T) {
+        *self
+            .get_mut(index)
+            .expect("RevisedSlice index out of bounds") = value;
+    }
+
+    /// Add an item to the end
+    pub fn push(&mut self, value: T) {
+        self.items.push(Revised::new(value));
+        self.revision.set(None);
+    }
+
+    /// Remove and return the last item, if any
+    pub fn pop(&mut self) -> Option<T> {
+        let item = self.items.pop()?;
+        self.revision.set(None);
+        Some(item.value)
+    }
+
+    /// Iterate over the items in order
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter().map(|item| &**item)
+    }
+
+    /// Get the RevisionHash of the whole sequence, using the cached
+    /// revisions of all items which weren't mutated since
+    pub fn get_revision(&self) -> RevisionHash {
+        match self.revision.get() {
+            Some(v) => v,
+            None => {
+                let v = sequence_revision(self.items.iter().map(|item| item.get_revision()));
+                self.revision.set(Some(v));
+                v
+            }
+        }
+    }
+}
+
+impl<T: Revisable> Default for RevisedSlice<T> {
+    fn default() -> RevisedSlice<T> {
+        RevisedSlice::new()
+    }
+}
+
+/// A RevisedSlice can be collected from an iterator of items
+impl<T: Revisable> FromIterator<T> for RevisedSlice<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> RevisedSlice<T> {
+        RevisedSlice {
+            items: iter.into_iter().map(Revised::new).collect(),
+            revision: Cell::new(None),
+        }
+    }
+}
+
+/// RevisedSlice<T> is Revisable using its cached revision
+impl<T: Revisable> Revisable for RevisedSlice<T> {
+    fn get_revision(&self) -> RevisionHash {
+        RevisedSlice::get_revision(self)
+    }
+}
@@ -0,0 +1,88 @@
+use core::{
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+
+use crate::{Revisable, RevisionHash};
+
+/// SyncRevised is like Revised<T>, except that its cached revision is
+/// stored in atomics rather than a Cell, so that it's Sync whenever T is
+/// and get_revision can be called concurrently from multiple threads, e.g.
+/// through an Arc. The revision is still computed lazily on first read and
+/// invalidated on mutable access. If several threads read an invalidated
+/// revision at the same time, each of them may compute it, but they all
+/// get the same result.
+pub struct SyncRevised<T> {
+    /// The stored object
+    value: T,
+
+    /// The revision hash of the stored object, if valid is set
+    revision: AtomicU64,
+
+    /// Whether the stored revision hash is up to date
+    valid: AtomicBool,
+}
+
+impl<T: Revisable> SyncRevised<T> {
+    /// Construct a new SyncRevised object containing the given object
+    pub fn new(value: T) -> SyncRevised<T> {
+        SyncRevised {
+            value,
+            revision: AtomicU64::new(0),
+            valid: AtomicBool::new(false),
+        }
+    }
+
+    /// Get the contained object's RevisionHash, computing it only if it's
+    /// not cached
+    pub fn get_revision(&self) -> RevisionHash {
+        if self.valid.load(Ordering::Acquire) {
+            return RevisionHash::new(self.revision.load(Ordering::Relaxed));
+        }
+        let v = self.value.get_revision();
+        self.revision.store(v.value(), Ordering::Relaxed);
+        self.valid.store(true, Ordering::Release);
+        v
+    }
+
+    /// Consume the SyncRevised and return the contained object
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/// Cloning a SyncRevised<T> clones the stored object and its cache
+impl<T: Clone> Clone for SyncRevised<T> {
+    fn clone(&self) -> Self {
+        SyncRevised {
+            value: self.value.clone(),
+            revision: AtomicU64::new(self.revision.load(Ordering::Relaxed)),
+            valid: AtomicBool::new(self.valid.load(Ordering::Acquire)),
+        }
+    }
+}
+
+/// SyncRevised<T> can deref to &T
+impl<T: Revisable> Deref for SyncRevised<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+/// SyncRevised<T> can deref to &mut T, which invalidates the cached
+/// revision
+impl<T: Revisable> DerefMut for SyncRevised<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        *self.valid.get_mut() = false;
+        &mut self.value
+    }
+}
+
+/// SyncRevised<T> is Revisable using its cached revision
+impl<T: Revisable> Revisable for SyncRevised<T> {
+    fn get_revision(&self) -> RevisionHash {
+        SyncRevised::get_revision(self)
+    }
+}
@@ -1,6 +1,22 @@
-use std::hash::Hasher as _;
+use std::{
+    borrow::{Borrow, BorrowMut, Cow},
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    hash::{BuildHasher, Hash, Hasher},
+    rc::Rc,
+    sync::Arc,
+};
 
-use crate::{Revisable, RevisionHash, RevisionHasher};
+use crate::{
+    assert_revisable_fields, diff_fields, interner_revision, invalidate_all, parse_etag,
+    partition_revision, revise_ordered, revise_unordered, revision_of_ordered_set, AppendLog,
+    ChannelRevised, CowRevised, HashAsRevision, InstrumentedHasher, MerkleRevised, Opaque,
+    ProjectionCache, PropertyNode, Revisable, Revisable128, RevisableClosure, RevisableDomain,
+    Revised, RevisedCache, RevisedGrid, RevisedNotify, RevisedProperty, RevisedRing, RevisedSlice,
+    RevisedVec, RevisionBuildHasher, RevisionCache, RevisionHash, RevisionHash128, RevisionHasher,
+    RevisionSink, RevisionWatcher, RowRevisionBuilder, SharedRevised, SyncRevised, TotalOrdFloat,
+    TransitionCounter, Versioned,
+};
 
 struct TestInt(i32);
 
@@ -12,6 +28,31 @@ impl Revisable for TestInt {
     }
 }
 
+/// Integer which counts how many times its revision was computed
+#[derive(Clone)]
+struct CountedInt {
+    value: i32,
+    counter: Rc<Cell<usize>>,
+}
+
+impl CountedInt {
+    fn new(value: i32, counter: &Rc<Cell<usize>>) -> CountedInt {
+        CountedInt {
+            value,
+            counter: Rc::clone(counter),
+        }
+    }
+}
+
+impl Revisable for CountedInt {
+    fn get_revision(&self) -> RevisionHash {
+        self.counter.set(self.counter.get() + 1);
+        let mut hasher = RevisionHasher::new();
+        hasher.write_i32(self.value);
+        hasher.into_revision()
+    }
+}
+
 #[test]
 fn basic_test() {
     let mut x = TestInt(1);
@@ -31,4 +72,3770 @@ fn basic_test() {
     assert_eq!(r1, r2);
 }
 
+#[test]
+fn hashmap_capacity_independent() {
+    let mut grown: HashMap<i32, i32> = HashMap::new();
+    for i in 0..1000 {
+        grown.insert(i, i * 2);
+    }
+    for i in 10..1000 {
+        grown.remove(&i);
+    }
+
+    let mut fresh: HashMap<i32, i32> = HashMap::new();
+    for i in 0..10 {
+        fresh.insert(i, i * 2);
+    }
+
+    assert_ne!(grown.capacity(), fresh.capacity());
+    assert_eq!(grown.get_revision(), fresh.get_revision());
+
+    let mut reserved: HashMap<i32, i32> = HashMap::with_capacity(4096);
+    reserved.extend(fresh.iter().map(|(k, v)| (*k, *v)));
+    assert_eq!(reserved.get_revision(), fresh.get_revision());
+
+    grown.shrink_to_fit();
+    assert_eq!(grown.get_revision(), fresh.get_revision());
+}
+
+#[test]
+fn revised_with_value() {
+    let original = Revised::new(TestInt(1));
+    let r_original = original.get_revision();
+
+    let mut derived = original.with_value(TestInt(5));
+    assert_eq!(derived.get_revision(), TestInt(5).get_revision());
+    assert_ne!(derived.get_revision(), r_original);
+
+    derived.0 = 6;
+    assert_eq!(derived.get_revision(), TestInt(6).get_revision());
+    assert_eq!(original.0, 1);
+    assert_eq!(original.get_revision(), r_original);
+}
+
+/// Borrowed half of a custom ToOwned pair
+struct TestView(i32);
+
+/// Owned half of a custom ToOwned pair
+struct TestOwned {
+    view: TestView,
+}
+
+impl Borrow<TestView> for TestOwned {
+    fn borrow(&self) -> &TestView {
+        &self.view
+    }
+}
+
+impl ToOwned for TestView {
+    type Owned = TestOwned;
+
+    fn to_owned(&self) -> TestOwned {
+        TestOwned {
+            view: TestView(self.0),
+        }
+    }
+}
+
+impl Revisable for TestView {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_i32(self.0);
+        hasher.into_revision()
+    }
+}
+
+#[test]
+fn cow_custom_to_owned() {
+    let view = TestView(7);
+    let borrowed: Cow<TestView> = Cow::Borrowed(&view);
+    let owned: Cow<TestView> = Cow::Owned(view.to_owned());
+
+    assert_eq!(borrowed.get_revision(), owned.get_revision());
+    assert_eq!(borrowed.get_revision(), view.get_revision());
+
+    let other: Cow<TestView> = Cow::Owned(TestView(8).to_owned());
+    assert_ne!(borrowed.get_revision(), other.get_revision());
+}
+
+#[test]
+fn cow_str_borrowed_and_owned() {
+    let borrowed: Cow<str> = Cow::Borrowed("hi");
+    let owned: Cow<str> = Cow::Owned(String::from("hi"));
+
+    assert_eq!(borrowed.get_revision(), owned.get_revision());
+    assert_eq!(borrowed.get_revision(), "hi".get_revision());
+    assert_eq!(owned.get_revision(), String::from("hi").get_revision());
+
+    let other: Cow<str> = Cow::Borrowed("ho");
+    assert_ne!(borrowed.get_revision(), other.get_revision());
+}
+
+/// Composite map key containing a float, which hashes and compares
+/// all NaNs as the same value
+#[derive(Clone, Copy)]
+struct TestFloatKey(i32, f64);
+
+impl TestFloatKey {
+    fn canonical_bits(&self) -> u64 {
+        if self.1.is_nan() {
+            f64::NAN.to_bits()
+        } else {
+            self.1.to_bits()
+        }
+    }
+}
+
+impl PartialEq for TestFloatKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0 && self.canonical_bits() == other.canonical_bits()
+    }
+}
+
+impl Eq for TestFloatKey {}
+
+impl Hash for TestFloatKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        self.canonical_bits().hash(state);
+    }
+}
+
+impl Revisable for TestFloatKey {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_i32(self.0);
+        hasher.write_u64(self.canonical_bits());
+        hasher.into_revision()
+    }
+}
+
+#[test]
+fn hashmap_nan_keys_deterministic() {
+    let build = |nan: f64| {
+        let mut map: HashMap<TestFloatKey, i32> = HashMap::new();
+        map.insert(TestFloatKey(0, 1.5), 10);
+        map.insert(TestFloatKey(1, nan), 20);
+        map.insert(TestFloatKey(2, -3.0), 30);
+        map
+    };
+
+    let a = build(f64::NAN);
+    let b = build(f64::from_bits(0x7ff8_0000_dead_beef));
+
+    let r = a.get_revision();
+    for _ in 0..10 {
+        assert_eq!(a.get_revision(), r);
+        assert_eq!(build(f64::NAN).get_revision(), r);
+    }
+    assert_eq!(b.get_revision(), r);
+
+    let mut c = build(f64::NAN);
+    c.insert(TestFloatKey(1, f64::NAN), 21);
+    assert_eq!(c.len(), 3);
+    assert_ne!(c.get_revision(), r);
+}
+
+#[test]
+fn revised_as_ref_as_mut() {
+    let mut x = Revised::new(TestInt(1));
+    let r0 = x.get_revision();
+
+    let inner: &TestInt = x.as_ref();
+    assert_eq!(inner.0, 1);
+    assert_eq!(x.get_revision(), r0);
+
+    x.as_mut().0 = 2;
+    let r1 = x.get_revision();
+    assert_ne!(r1, r0);
+    assert_eq!(r1, TestInt(2).get_revision());
+
+    let inner_mut: &mut TestInt = x.borrow_mut();
+    inner_mut.0 = 3;
+    assert_eq!(x.get_revision(), TestInt(3).get_revision());
+}
+
+#[test]
+fn revised_borrow_map_lookup() {
+    let mut map: HashMap<i32, &str> = HashMap::new();
+    map.insert(5, "five");
+
+    fn lookup<'a, K: Borrow<i32>>(map: &HashMap<i32, &'a str>, key: &K) -> Option<&'a str> {
+        map.get(key.borrow()).copied()
+    }
+
+    let key = Revised::new(5);
+    assert_eq!(lookup(&map, &key), Some("five"));
+    assert_eq!(map.get::<i32>(key.borrow()), Some(&"five"));
+}
+
+#[test]
+fn revised_string_borrow_and_as_ref() {
+    let map: HashMap<String, usize> = [(String::from("apple"), 1), (String::from("pear"), 2)]
+        .into_iter()
+        .collect();
+
+    fn count<K: Borrow<String>>(map: &HashMap<String, usize>, keys: &[K]) -> usize {
+        keys.iter().filter_map(|k| map.get(k.borrow())).sum()
+    }
+
+    fn total_len(items: &[impl AsRef<String>]) -> usize {
+        items.iter().map(|s| s.as_ref().len()).sum()
+    }
+
+    let keys = vec![
+        Revised::new(String::from("apple")),
+        Revised::new(String::from("pear")),
+        Revised::new(String::from("plum")),
+    ];
+    assert_eq!(count(&map, &keys), 3);
+    assert_eq!(map.get::<String>(keys[1].borrow()), Some(&2));
+    assert_eq!(total_len(&keys), 13);
+
+    // Borrowing doesn't invalidate the cached revision
+    let r0 = keys[0].get_revision();
+    let _: &String = keys[0].borrow();
+    assert!(!keys[0].is_dirty());
+    assert_eq!(keys[0].get_revision(), r0);
+}
+
+#[test]
+fn opaque_hashes_once() {
+    let counter = Rc::new(Cell::new(0));
+    let subtree = Opaque::new(vec![
+        CountedInt::new(1, &counter),
+        CountedInt::new(2, &counter),
+    ]);
+    assert_eq!(counter.get(), 2);
+
+    let tree = (TestInt(0), &subtree);
+    let r0 = tree.get_revision();
+    for _ in 0..10 {
+        assert_eq!(tree.get_revision(), r0);
+    }
+    assert_eq!(counter.get(), 2);
+
+    assert_eq!(
+        subtree.get_revision(),
+        vec![CountedInt::new(1, &counter), CountedInt::new(2, &counter)].get_revision()
+    );
+    assert_eq!(subtree[1].value, 2);
+}
+
+#[test]
+fn revised_ring_window() {
+    let counter = Rc::new(Cell::new(0));
+    let mut ring = RevisedRing::new(3);
+    let mut window: VecDeque<i32> = VecDeque::new();
+
+    let window_revision = |window: &VecDeque<i32>| {
+        let items: Vec<TestInt> = window.iter().map(|v| TestInt(*v)).collect();
+        items.get_revision()
+    };
+
+    assert_eq!(ring.get_revision(), window_revision(&window));
+
+    let mut previous = ring.get_revision();
+    for v in 0..6 {
+        let evicted = ring.push(CountedInt::new(v, &counter));
+        window.push_back(v);
+        if window.len() > 3 {
+            assert_eq!(evicted.map(|e| e.value), window.pop_front());
+        } else {
+            assert!(evicted.is_none());
+        }
+
+        let r = ring.get_revision();
+        assert_ne!(r, previous);
+        assert_eq!(r, window_revision(&window));
+        previous = r;
+    }
+
+    // Each item is hashed only once, when pushed
+    assert_eq!(counter.get(), 6);
+    assert_eq!(ring.len(), 3);
+    assert_eq!(ring.front().unwrap().value, 3);
+    assert_eq!(ring.back().unwrap().value, 5);
+}
+
+/// Set element whose identity is its id but whose revision is fixed,
+/// so that different elements can be made to have equal hashes
+#[derive(PartialEq, Eq, Hash)]
+struct TestCollidingItem {
+    id: u32,
+    revision: u64,
+}
+
+impl Revisable for TestCollidingItem {
+    fn get_revision(&self) -> RevisionHash {
+        RevisionHash::new(self.revision)
+    }
+}
+
+#[test]
+fn hashset_colliding_items_dont_cancel() {
+    let empty: HashSet<TestCollidingItem> = HashSet::new();
+
+    let a: HashSet<TestCollidingItem> = [
+        TestCollidingItem { id: 0, revision: 7 },
+        TestCollidingItem { id: 1, revision: 7 },
+    ]
+    .into_iter()
+    .collect();
+
+    let b: HashSet<TestCollidingItem> = [
+        TestCollidingItem { id: 2, revision: 9 },
+        TestCollidingItem { id: 3, revision: 9 },
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(a.len(), 2);
+    assert_ne!(a.get_revision(), empty.get_revision());
+    assert_ne!(a.get_revision(), b.get_revision());
+}
+
+#[test]
+fn hashset_order_independent() {
+    let mut a: HashSet<i32> = HashSet::new();
+    let mut b: HashSet<i32> = HashSet::with_capacity(100);
+    for i in 0..50 {
+        a.insert(i);
+        b.insert(49 - i);
+    }
+    assert_eq!(a.get_revision(), b.get_revision());
+
+    b.remove(&10);
+    assert_ne!(a.get_revision(), b.get_revision());
+}
+
+#[test]
+fn revised_grid_revision_is_flat() {
+    let grid = RevisedGrid::from_fn(2, 3, |row, col| TestInt((row * 3 + col) as i32));
+
+    // The dimensions followed by every cell in row-major order
+    let mut hasher = RevisionHasher::new();
+    hasher.write_usize(2);
+    hasher.write_usize(3);
+    for i in 0..6 {
+        hasher.write_revisable(&TestInt(i));
+    }
+    assert_eq!(grid.get_revision(), hasher.into_revision());
+
+    // The same cells with different dimensions differ
+    let wide = RevisedGrid::from_fn(1, 6, |_, col| TestInt(col as i32));
+    assert_ne!(grid.get_revision(), wide.get_revision());
+}
+
+#[test]
+#[cfg_attr(
+    all(feature = "debug-purity-checks", debug_assertions),
+    ignore = "counts revision computations, which purity checks double"
+)]
+fn revised_grid_incremental() {
+    let counter = Rc::new(Cell::new(0));
+    let mut grid = RevisedGrid::from_fn(4, 5, |row, col| {
+        CountedInt::new((row * 10 + col) as i32, &counter)
+    });
+
+    // Naive full rehash of the same content, flat in row-major order
+    let naive = |grid: &RevisedGrid<CountedInt>| {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_usize(grid.rows());
+        hasher.write_usize(grid.cols());
+        for row in 0..grid.rows() {
+            for col in 0..grid.cols() {
+                hasher.write_revisable(&TestInt(grid.get(row, col).unwrap().value));
+            }
+        }
+        hasher.into_revision()
+    };
+
+    let r0 = grid.get_revision();
+    assert_eq!(r0, naive(&grid));
+    assert_eq!(counter.get(), 20);
+
+    grid.get_mut(2, 3).unwrap().value = 99;
+    let r1 = grid.get_revision();
+    assert_ne!(r1, r0);
+    assert_eq!(r1, naive(&grid));
+
+    // Only the edited cell was rehashed
+    assert_eq!(counter.get(), 21);
+
+    assert_eq!(
+        grid.col_revision(3),
+        vec![TestInt(3), TestInt(13), TestInt(99), TestInt(33)].get_revision()
+    );
+
+    grid.set(2, 3, CountedInt::new(23, &counter));
+    assert_eq!(grid.get_revision(), r0);
+    assert!(grid.get(4, 0).is_none());
+}
+
+/// Tree node with an optional child of any Revisable type
+struct TestDynNode {
+    value: i32,
+    child: Option<Box<dyn Revisable>>,
+}
+
+impl Revisable for TestDynNode {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_i32(self.value);
+        hasher.write_revisable(&self.child);
+        hasher.into_revision()
+    }
+}
+
+#[test]
+fn optional_dyn_child() {
+    let leaf = TestDynNode {
+        value: 1,
+        child: None,
+    };
+    let with_child = TestDynNode {
+        value: 1,
+        child: Some(Box::new(TestInt(2))),
+    };
+    let with_same_child = TestDynNode {
+        value: 1,
+        child: Some(Box::new(TestInt(2))),
+    };
+    let with_other_child = TestDynNode {
+        value: 1,
+        child: Some(Box::new(TestInt(3))),
+    };
+    let with_nested_child = TestDynNode {
+        value: 1,
+        child: Some(Box::new(TestDynNode {
+            value: 2,
+            child: None,
+        })),
+    };
+
+    assert_ne!(leaf.get_revision(), with_child.get_revision());
+    assert_eq!(with_child.get_revision(), with_same_child.get_revision());
+    assert_ne!(with_child.get_revision(), with_other_child.get_revision());
+    assert_ne!(with_child.get_revision(), with_nested_child.get_revision());
+}
+
+#[test]
+fn revision_build_hasher() {
+    let builder = RevisionBuildHasher;
+
+    let mut h0 = builder.build_hasher();
+    let mut h1 = builder.build_hasher();
+    h0.write_u32(42);
+    h1.write_u32(42);
+    assert_eq!(h0.finish(), h1.finish());
+
+    assert_eq!(builder.hash_one("some key"), builder.hash_one("some key"));
+    assert_ne!(builder.hash_one("some key"), builder.hash_one("other key"));
+
+    let mut map: HashMap<i32, i32, RevisionBuildHasher> = HashMap::default();
+    map.insert(1, 2);
+    assert_eq!(map.get(&1), Some(&2));
+}
+
+#[test]
+fn interner_revision_order_independent() {
+    let a: Vec<Rc<str>> = vec!["apple".into(), "banana".into(), "cherry".into()];
+    let b: Vec<Rc<str>> = vec!["cherry".into(), "apple".into(), "banana".into()];
+    assert_eq!(interner_revision(&a), interner_revision(&b));
+    assert_eq!(
+        interner_revision(&a),
+        interner_revision(["banana", "cherry", "apple"])
+    );
+
+    assert_ne!(
+        interner_revision(&a),
+        interner_revision(["apple", "banana"])
+    );
+    assert_ne!(
+        interner_revision(["ab", "c"]),
+        interner_revision(["a", "bc"])
+    );
+}
+
+#[test]
+fn revised_projection() {
+    let counter = Rc::new(Cell::new(0));
+    let mut parent = Revised::new((CountedInt::new(1, &counter), TestInt(2)));
+
+    {
+        let projection = parent.project(|p| &p.0);
+        let r = projection.get_revision();
+        assert_eq!(r, CountedInt::new(1, &counter).get_revision());
+        assert_eq!(counter.get(), 2);
+
+        for _ in 0..10 {
+            assert_eq!(projection.get_revision(), r);
+        }
+        assert_eq!(counter.get(), 2);
+        assert_eq!(projection.get().value, 1);
+    }
+
+    // Changing another field doesn't change the projection's revision
+    let r_first = parent.project(|p| &p.0).get_revision();
+    parent.1 = TestInt(3);
+    assert_eq!(parent.project(|p| &p.0).get_revision(), r_first);
+
+    parent.0.value = 4;
+    assert_ne!(parent.project(|p| &p.0).get_revision(), r_first);
+}
+
+#[test]
+#[cfg_attr(
+    all(feature = "debug-purity-checks", debug_assertions),
+    ignore = "counts revision computations, which purity checks double"
+)]
+fn revised_projection_cached() {
+    let counter = Rc::new(Cell::new(0));
+    let mut parent = Revised::new((CountedInt::new(1, &counter), TestInt(2)));
+    let cache = ProjectionCache::new();
+    let r0 = parent.get_revision();
+    assert_eq!(counter.get(), 1);
+
+    // The parent is clean, so only the first projection hashes the part
+    let r = parent.project_cached(&cache, |p| &p.0).get_revision();
+    assert_eq!(r, TestInt(1).get_revision());
+    assert_eq!(counter.get(), 2);
+    for _ in 0..10 {
+        assert_eq!(parent.project_cached(&cache, |p| &p.0).get_revision(), r);
+    }
+    assert_eq!(counter.get(), 2);
+    assert_eq!(parent.get_revision(), r0);
+
+    // A dirty parent can't be used as the key
+    parent.0.value = 4;
+    let r1 = parent.project_cached(&cache, |p| &p.0).get_revision();
+    assert_eq!(r1, TestInt(4).get_revision());
+    assert_eq!(counter.get(), 3);
+    parent.project_cached(&cache, |p| &p.0).get_revision();
+    assert_eq!(counter.get(), 4);
+
+    // Once the parent is clean again, the cache is keyed on its new revision
+    parent.get_revision();
+    assert_eq!(counter.get(), 5);
+    assert_eq!(parent.project_cached(&cache, |p| &p.0).get_revision(), r1);
+    assert_eq!(parent.project_cached(&cache, |p| &p.0).get_revision(), r1);
+    assert_eq!(counter.get(), 6);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn revised_property_serde_round_trip() {
+    let calls = Cell::new(0);
+    let f = |x: TestInt| {
+        calls.set(calls.get() + 1);
+        x.0 * 10
+    };
+
+    let mut property = RevisedProperty::new();
+    property.refresh1(f, TestInt(4));
+    assert_eq!(calls.get(), 1);
+
+    let saved = serde_json::to_string(&property).unwrap();
+    let mut loaded: RevisedProperty<i32> = serde_json::from_str(&saved).unwrap();
+    assert_eq!(loaded.get_cached(), Some(&40));
+
+    // Same arguments after loading: cache hit
+    loaded.refresh1(f, TestInt(4));
+    assert_eq!(calls.get(), 1);
+
+    // Different arguments after loading: cache miss
+    loaded.refresh1(f, TestInt(5));
+    assert_eq!(calls.get(), 2);
+    assert_eq!(loaded.get_cached(), Some(&50));
+
+    let empty: RevisedProperty<i32> =
+        serde_json::from_str(&serde_json::to_string(&RevisedProperty::<i32>::new()).unwrap())
+            .unwrap();
+    assert_eq!(empty.get_cached(), None);
+}
+
+/// Expression DAG node whose children may be shared
+enum TestExpr {
+    Leaf(CountedInt),
+    Add(Rc<TestExpr>, Rc<TestExpr>),
+}
+
+fn test_expr_revision(expr: &Rc<TestExpr>, cache: &mut RevisionCache) -> RevisionHash {
+    cache.get_or_insert_with(expr, |cache| {
+        let mut hasher = RevisionHasher::new();
+        match &**expr {
+            TestExpr::Leaf(x) => {
+                hasher.write_u8(0);
+                hasher.write_revisable(x);
+            }
+            TestExpr::Add(a, b) => {
+                hasher.write_u8(1);
+                hasher.write_revision(test_expr_revision(a, cache));
+                hasher.write_revision(test_expr_revision(b, cache));
+            }
+        }
+        hasher.into_revision()
+    })
+}
+
+#[test]
+fn revision_cache_diamond() {
+    let counter = Rc::new(Cell::new(0));
+
+    // Diamond: the root's two children share the same leaf
+    let shared = Rc::new(TestExpr::Leaf(CountedInt::new(1, &counter)));
+    let left = Rc::new(TestExpr::Add(Rc::clone(&shared), Rc::clone(&shared)));
+    let right = Rc::new(TestExpr::Add(
+        Rc::clone(&shared),
+        Rc::new(TestExpr::Leaf(CountedInt::new(2, &counter))),
+    ));
+    let root = Rc::new(TestExpr::Add(left, right));
+
+    let mut cache = RevisionCache::new();
+    let r0 = test_expr_revision(&root, &mut cache);
+    assert_eq!(counter.get(), 2);
+    assert_eq!(cache.len(), 5);
+
+    // A separate structure with the same shape but no sharing
+    let unshared_counter = Rc::new(Cell::new(0));
+    let leaf = |v| Rc::new(TestExpr::Leaf(CountedInt::new(v, &unshared_counter)));
+    let unshared = Rc::new(TestExpr::Add(
+        Rc::new(TestExpr::Add(leaf(1), leaf(1))),
+        Rc::new(TestExpr::Add(leaf(1), leaf(2))),
+    ));
+    let r1 = test_expr_revision(&unshared, &mut RevisionCache::new());
+    assert_eq!(unshared_counter.get(), 4);
+    assert_eq!(r0, r1);
+}
+
+#[test]
+fn hashmap_of_refcells() {
+    let mut map: HashMap<i32, RefCell<TestInt>> = HashMap::new();
+    map.insert(1, RefCell::new(TestInt(10)));
+    map.insert(2, RefCell::new(TestInt(20)));
+
+    let r0 = map.get_revision();
+
+    map[&2].borrow_mut().0 = 21;
+    let r1 = map.get_revision();
+    assert_ne!(r1, r0);
+
+    map[&2].borrow_mut().0 = 20;
+    assert_eq!(map.get_revision(), r0);
+}
+
+#[test]
+fn cell_revision() {
+    let cell = Cell::new(5_u32);
+    let r0 = cell.get_revision();
+    assert_eq!(r0, 5_u32.get_revision());
+
+    cell.set(6);
+    assert_ne!(cell.get_revision(), r0);
+
+    cell.set(5);
+    assert_eq!(cell.get_revision(), r0);
+}
+
+#[test]
+#[should_panic]
+fn refcell_revision_while_mutably_borrowed() {
+    let cell = RefCell::new(TestInt(1));
+    let _guard = cell.borrow_mut();
+    cell.get_revision();
+}
+
+/// Integer which counts how many times it was cloned
+struct CloneCountedInt {
+    value: i32,
+    clones: Rc<Cell<usize>>,
+}
+
+impl Clone for CloneCountedInt {
+    fn clone(&self) -> Self {
+        self.clones.set(self.clones.get() + 1);
+        CloneCountedInt {
+            value: self.value,
+            clones: Rc::clone(&self.clones),
+        }
+    }
+}
+
+impl Revisable for CloneCountedInt {
+    fn get_revision(&self) -> RevisionHash {
+        TestInt(self.value).get_revision()
+    }
+}
+
+#[test]
+fn cow_revised_snapshots() {
+    let clones = Rc::new(Cell::new(0));
+    let mut current = CowRevised::new(CloneCountedInt {
+        value: 1,
+        clones: Rc::clone(&clones),
+    });
+    let r0 = current.get_revision();
+    assert_eq!(r0, Revised::new(TestInt(1)).get_revision());
+
+    let snapshots: Vec<CowRevised<CloneCountedInt>> = (0..10).map(|_| current.clone()).collect();
+    assert_eq!(clones.get(), 0);
+    assert!(CowRevised::ptr_eq(&current, &snapshots[0]));
+
+    current.value = 2;
+    assert_eq!(clones.get(), 1);
+    assert!(!CowRevised::ptr_eq(&current, &snapshots[0]));
+    assert_eq!(current.get_revision(), TestInt(2).get_revision());
+    for snapshot in &snapshots {
+        assert_eq!(snapshot.value, 1);
+        assert_eq!(snapshot.get_revision(), r0);
+    }
+
+    // No longer shared, so mutating again doesn't clone
+    drop(snapshots);
+    current.value = 3;
+    assert_eq!(clones.get(), 1);
+    assert_eq!(current.get_revision(), TestInt(3).get_revision());
+}
+
+/// Enum mixing unit variants, which use the fast path, and data variants
+enum TestShape {
+    Empty,
+    Unknown,
+    Circle(i32),
+    Rect(i32, i32),
+}
+
+impl Revisable for TestShape {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        match self {
+            TestShape::Empty => return RevisionHash::unit_variant(0),
+            TestShape::Unknown => return RevisionHash::unit_variant(1),
+            TestShape::Circle(r) => {
+                hasher.write_u32(2);
+                hasher.write_i32(*r);
+            }
+            TestShape::Rect(w, h) => {
+                hasher.write_u32(3);
+                hasher.write_i32(*w);
+                hasher.write_i32(*h);
+            }
+        }
+        hasher.into_revision()
+    }
+}
+
+#[test]
+fn unit_variant_fast_path() {
+    const EMPTY: RevisionHash = RevisionHash::unit_variant(0);
+    assert_eq!(TestShape::Empty.get_revision(), EMPTY);
+    assert_ne!(
+        TestShape::Empty.get_revision(),
+        TestShape::Unknown.get_revision()
+    );
+
+    let mut seen = HashSet::new();
+    for d in 0..1000 {
+        assert!(seen.insert(RevisionHash::unit_variant(d)));
+    }
+    for v in -100..100 {
+        assert!(seen.insert(TestShape::Circle(v).get_revision()));
+        assert!(seen.insert(TestShape::Rect(v, 1).get_revision()));
+    }
+
+    // Hashing just a discriminant with a hasher doesn't match either
+    for d in 0..1000 {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_u32(d);
+        assert!(seen.insert(hasher.into_revision()));
+    }
+}
+
+#[test]
+fn versioned_revisions() {
+    let v1: Versioned<1, TestInt> = Versioned::new(TestInt(5));
+    let v1_again: Versioned<1, TestInt> = Versioned(TestInt(5));
+    let v2: Versioned<2, TestInt> = Versioned::new(TestInt(5));
+
+    assert_eq!(v1.get_revision(), v1_again.get_revision());
+    assert_ne!(v1.get_revision(), v2.get_revision());
+    assert_ne!(v1.get_revision(), TestInt(5).get_revision());
+    assert_eq!(Versioned::<2, TestInt>::VERSION, 2);
+
+    let mut v2 = v2;
+    v2.0 = TestInt(6);
+    assert_ne!(
+        v2.get_revision(),
+        Versioned::<2, _>(TestInt(5)).get_revision()
+    );
+}
+
+#[test]
+fn revisable_closure() {
+    fn scale(factor: &TestInt, x: i32) -> i32 {
+        factor.0 * x
+    }
+
+    let a = RevisableClosure::new(TestInt(3), scale);
+    let b = RevisableClosure::new(TestInt(3), scale);
+    assert_eq!(a.get_revision(), b.get_revision());
+    assert_eq!(a.call(2), 6);
+
+    let mut c = RevisableClosure::new(TestInt(3), |factor: &TestInt, (x, y): (i32, i32)| {
+        factor.0 * (x + y)
+    });
+    assert_eq!(c.get_revision(), a.get_revision());
+    assert_eq!(c.call((1, 2)), 9);
+
+    c.capture_mut().0 = 4;
+    assert_ne!(c.get_revision(), a.get_revision());
+    assert_eq!(c.call((1, 2)), 12);
+}
+
+#[test]
+#[cfg_attr(
+    all(feature = "debug-purity-checks", debug_assertions),
+    ignore = "counts revision computations, which purity checks double"
+)]
+fn revised_invalidate() {
+    let counter = Rc::new(Cell::new(0));
+    let mut items: Vec<Revised<CountedInt>> = (0..4)
+        .map(|i| Revised::new(CountedInt::new(i, &counter)))
+        .collect();
+
+    let r0 = items.get_revision();
+    assert_eq!(counter.get(), 4);
+    items.get_revision();
+    assert_eq!(counter.get(), 4);
+
+    items[0].invalidate();
+    assert_eq!(items.get_revision(), r0);
+    assert_eq!(counter.get(), 5);
+
+    invalidate_all(&mut items);
+    assert_eq!(items.get_revision(), r0);
+    assert_eq!(counter.get(), 9);
+    items.get_revision();
+    assert_eq!(counter.get(), 9);
+}
+
+#[test]
+fn revised_with_revision() {
+    let counter = Rc::new(Cell::new(0));
+    let revision = TestInt(3).get_revision();
+    let item = Revised::with_revision(CountedInt::new(3, &counter), revision);
+    assert_eq!(item.peek_revision(), Some(revision));
+
+    let computed = counter.get();
+    assert_eq!(item.get_revision(), revision);
+    assert_eq!(counter.get(), computed);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "doesn't match")]
+fn revised_with_wrong_revision() {
+    Revised::with_revision(TestInt(3), TestInt(4).get_revision());
+}
+
+#[test]
+fn revised_replace() {
+    let mut item = Revised::new(TestInt(1));
+    let r1 = item.get_revision();
+    assert!(!item.is_dirty());
+
+    let old = item.replace(TestInt(2));
+    assert_eq!(old.0, 1);
+    assert!(item.is_dirty());
+    assert_eq!(item.get_revision(), TestInt(2).get_revision());
+    assert_ne!(item.get_revision(), r1);
+
+    item.get_mut().0 = 1;
+    assert!(item.is_dirty());
+    assert_eq!(item.get_revision(), r1);
+}
+
+/// Minimal union-find over 0..n
+struct TestUnionFind {
+    parents: Vec<usize>,
+}
+
+impl TestUnionFind {
+    fn new(n: usize) -> TestUnionFind {
+        TestUnionFind {
+            parents: (0..n).collect(),
+        }
+    }
+
+    fn find(&self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        self.parents[ra] = rb;
+    }
+
+    fn revision(&self) -> RevisionHash {
+        partition_revision((0..self.parents.len()).map(|i| (i, self.find(i))))
+    }
+}
+
+#[test]
+fn partition_revision_union_orders() {
+    let mut a = TestUnionFind::new(6);
+    a.union(0, 1);
+    a.union(1, 2);
+    a.union(3, 4);
+
+    let mut b = TestUnionFind::new(6);
+    b.union(4, 3);
+    b.union(2, 0);
+    b.union(1, 0);
+
+    assert_ne!(a.parents, b.parents);
+    assert_eq!(a.revision(), b.revision());
+
+    b.union(5, 3);
+    assert_ne!(a.revision(), b.revision());
+
+    // Same class sizes, different grouping
+    let mut c = TestUnionFind::new(6);
+    c.union(0, 1);
+    c.union(1, 3);
+    c.union(2, 4);
+    assert_ne!(a.revision(), c.revision());
+}
+
+#[test]
+fn etag_round_trip() {
+    let mut x = Revised::new(TestInt(1));
+    let etag = x.etag();
+    assert_eq!(etag.len(), 18);
+    assert!(etag.starts_with('"') && etag.ends_with('"'));
+    assert_eq!(x.etag(), etag);
+    assert_eq!(parse_etag(&etag), Some(x.get_revision()));
+    assert_eq!(parse_etag(&format!("W/{}", etag)), Some(x.get_revision()));
+
+    x.0 = 2;
+    assert_ne!(x.etag(), etag);
+    assert_eq!(parse_etag(&x.etag()), Some(x.get_revision()));
+
+    assert_eq!(
+        TestCollidingItem {
+            id: 0,
+            revision: 0xab
+        }
+        .etag(),
+        "\"00000000000000ab\""
+    );
+    assert_eq!(parse_etag("00000000000000ab"), None);
+    assert_eq!(parse_etag("\"xyz\""), None);
+    assert_eq!(parse_etag("\"\""), None);
+    assert_eq!(parse_etag("\"+ab\""), None);
+}
+
+#[test]
+fn instrumented_hasher() {
+    let mut hasher = InstrumentedHasher::new();
+    let mut plain = RevisionHasher::new();
+
+    hasher.write_i32(5);
+    plain.write_i32(5);
+    hasher.write_u8(1);
+    plain.write_u8(1);
+    hasher.write_revisable(&TestInt(2));
+    plain.write_revisable(&TestInt(2));
+    hasher.write(b"abc");
+    plain.write(b"abc");
+
+    assert_eq!(hasher.write_count(), 4);
+    assert_eq!(hasher.bytes_written(), 4 + 1 + 8 + 3);
+    assert_eq!(hasher.into_revision(), plain.into_revision());
+}
+
+#[test]
+fn append_log_incremental() {
+    let counter = Rc::new(Cell::new(0));
+    let mut log = AppendLog::new();
+    let mut plain: Vec<TestInt> = Vec::new();
+    assert_eq!(log.get_revision(), plain.get_revision());
+
+    for i in 0..20 {
+        log.push(CountedInt::new(i * 3, &counter));
+        plain.push(TestInt(i * 3));
+        assert_eq!(log.get_revision(), plain.get_revision());
+        assert_eq!(log.get_revision(), plain.as_slice().get_revision());
+    }
+
+    // Each item was hashed exactly once, when appended
+    assert_eq!(counter.get(), 20);
+    assert_eq!(log.len(), 20);
+    assert_eq!(log.get(4).unwrap().value, 12);
+
+    // Slices hash their length first, followed by their items
+    let mut hasher = RevisionHasher::new();
+    hasher.write_usize(20);
+    for i in 0..20 {
+        hasher.write_revisable(&TestInt(i * 3));
+    }
+    assert_eq!(log.get_revision(), hasher.into_revision());
+}
+
+#[test]
+fn write_some_chain() {
+    let a = Some(TestInt(1));
+    let b: Option<TestInt> = None;
+    let c = Some(TestInt(3));
+
+    let mut hasher = RevisionHasher::new();
+    hasher.write_some(&a).write_some(&b).write_some(&c);
+    assert_eq!(hasher.into_revision(), (&a, &b, &c).get_revision());
+
+    let mut swapped = RevisionHasher::new();
+    swapped.write_some(&b).write_some(&a).write_some(&c);
+    assert_ne!(swapped.into_revision(), (&a, &b, &c).get_revision());
+}
+
+#[test]
+fn sparse_tilemap() {
+    let tiles = [((0, 0), 1), ((3, -2), 2), ((-7, 5), 3), ((2, 3), 4)];
+
+    let forward: HashMap<(i32, i32), TestInt> =
+        tiles.iter().map(|&(c, t)| (c, TestInt(t))).collect();
+    let backward: HashMap<(i32, i32), TestInt> =
+        tiles.iter().rev().map(|&(c, t)| (c, TestInt(t))).collect();
+    assert_eq!(forward.get_revision(), backward.get_revision());
+
+    // Moving a tile changes the revision
+    let mut moved: HashMap<(i32, i32), TestInt> =
+        forward.iter().map(|(&c, t)| (c, TestInt(t.0))).collect();
+    let tile = moved.remove(&(2, 3)).unwrap();
+    moved.insert((2, 4), tile);
+    assert_ne!(moved.get_revision(), forward.get_revision());
+
+    // Coordinates are order-sensitive within the key
+    let mut transposed: HashMap<(i32, i32), TestInt> =
+        forward.iter().map(|(&c, t)| (c, TestInt(t.0))).collect();
+    let tile = transposed.remove(&(2, 3)).unwrap();
+    transposed.insert((3, 2), tile);
+    assert_ne!(transposed.get_revision(), forward.get_revision());
+    assert_ne!((2, 3).get_revision(), (3, 2).get_revision());
+}
+
+#[test]
+fn shared_revised_detaches_on_write() {
+    let clones = Rc::new(Cell::new(0));
+    let computed = Rc::new(Cell::new(0));
+    let mut a = SharedRevised::new((
+        CloneCountedInt {
+            value: 1,
+            clones: Rc::clone(&clones),
+        },
+        CountedInt::new(10, &computed),
+    ));
+    let b = a.clone();
+    assert!(SharedRevised::ptr_eq(&a, &b));
+
+    // The cache is shared while both are unchanged
+    let r0 = b.get_revision();
+    assert_eq!(a.get_revision(), r0);
+    assert_eq!(computed.get(), 1);
+    assert_eq!(clones.get(), 0);
+
+    // Mutating one clone copies the value and detaches its cache
+    a.0.value = 2;
+    assert_eq!(clones.get(), 1);
+    assert!(!SharedRevised::ptr_eq(&a, &b));
+    assert!(!SharedRevised::is_shared(&b));
+    assert_ne!(a.get_revision(), r0);
+    assert_eq!(computed.get(), 2);
+
+    // The sibling's cached revision is still valid and not recomputed
+    assert_eq!(b.get_revision(), r0);
+    assert_eq!(b.0.value, 1);
+    assert_eq!(computed.get(), 2);
+
+    // No longer shared, so mutating again doesn't clone
+    a.1.value = 11;
+    assert_eq!(clones.get(), 1);
+    assert_eq!(
+        a.get_revision(),
+        (TestInt(2), CountedInt::new(11, &computed)).get_revision()
+    );
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TestState {
+    Idle,
+    Running,
+}
+
+struct TestMachine {
+    state: TestState,
+    transitions: TransitionCounter,
+}
+
+impl TestMachine {
+    fn transition(&mut self, state: TestState) {
+        self.state = state;
+        self.transitions.bump();
+    }
+}
+
+impl Revisable for TestMachine {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_u8(self.state as u8);
+        hasher.write_revisable(&self.transitions);
+        hasher.into_revision()
+    }
+}
+
+#[test]
+fn transition_counter_aba() {
+    let mut machine = TestMachine {
+        state: TestState::Idle,
+        transitions: TransitionCounter::new(),
+    };
+    let r_a1 = machine.get_revision();
+
+    machine.transition(TestState::Running);
+    let r_b = machine.get_revision();
+
+    machine.transition(TestState::Idle);
+    let r_a2 = machine.get_revision();
+
+    assert_ne!(r_a1, r_b);
+    assert_ne!(r_b, r_a2);
+    assert_ne!(r_a1, r_a2);
+    assert_eq!(machine.transitions.count(), 2);
+
+    // Changing nothing without a bump keeps the revision
+    machine.state = TestState::Idle;
+    assert_eq!(machine.get_revision(), r_a2);
+}
+
+#[cfg(feature = "derive")]
+#[derive(crate::Revisable)]
+#[revise(domain = 0x6d65_7465_7273_0001)]
+struct TestMeters(u64);
+
+#[cfg(feature = "derive")]
+#[derive(crate::Revisable)]
+#[revise(domain = 0x7365_636f_6e64_0002)]
+struct TestSeconds(u64);
+
+#[cfg(feature = "derive")]
+#[derive(crate::Revisable)]
+#[revise(domain = 0)]
+struct TestPlain(u64);
+
+#[cfg(feature = "derive")]
+#[derive(crate::Revisable)]
+struct TestUndomained(u64);
+
+#[cfg(feature = "derive")]
+#[test]
+fn domain_separation() {
+    assert_ne!(TestMeters(5).get_revision(), TestSeconds(5).get_revision());
+    assert_ne!(
+        TestMeters(5).get_revision(),
+        TestUndomained(5).get_revision()
+    );
+
+    // The domain tag is hashed before the fields
+    let mut hasher = RevisionHasher::with_domain(TestMeters::DOMAIN);
+    hasher.write_revisable(&5_u64);
+    assert_eq!(TestMeters(5).get_revision(), hasher.into_revision());
+
+    // The default domain of zero leaves the revision unchanged
+    assert_eq!(TestPlain::DOMAIN, 0);
+    assert_eq!(
+        TestPlain(5).get_revision(),
+        TestUndomained(5).get_revision()
+    );
+}
+
+#[test]
+fn tracking_recomputes_only_read_sources() {
+    use crate::tracking::{Computed, Signal};
+
+    let a = Signal::new(TestInt(1));
+    let b = Signal::new(TestInt(100));
+    let calls = Rc::new(Cell::new(0));
+
+    let doubled = {
+        let a = a.clone();
+        let calls = Rc::clone(&calls);
+        Computed::new(move || {
+            calls.set(calls.get() + 1);
+            a.with(|x| x.0 * 2)
+        })
+    };
+    assert_eq!(calls.get(), 0);
+    assert_eq!(*doubled.get(), 2);
+    assert_eq!(*doubled.get(), 2);
+    assert_eq!(calls.get(), 1);
+    assert_eq!(doubled.dependency_count(), 1);
+
+    // Changing a source that wasn't read doesn't recompute
+    b.set(TestInt(200));
+    assert_eq!(*doubled.get(), 2);
+    assert_eq!(calls.get(), 1);
+
+    // Changing the source that was read does
+    a.modify(|x| x.0 = 5);
+    assert_eq!(*doubled.get(), 10);
+    assert_eq!(calls.get(), 2);
+
+    // Computeds can depend on other computeds
+    let sum = {
+        let doubled = doubled.clone();
+        let b = b.clone();
+        Computed::new(move || *doubled.get() + b.with(|x| x.0))
+    };
+    assert_eq!(*sum.get(), 210);
+    assert_eq!(sum.dependency_count(), 2);
+    a.set(TestInt(6));
+    assert_eq!(*sum.get(), 212);
+    assert_eq!(calls.get(), 3);
+}
+
+/// Shuffle a slice in place using a xorshift generator with the given seed
+fn test_shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed | 1;
+    for i in (1..items.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        items.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
+
+#[test]
+fn hashmap_insertion_order_stress() {
+    let mut entries: Vec<(i32, i32)> = (0..300).map(|i| (i * 7 - 1000, i * i)).collect();
+    let reference: HashMap<i32, i32> = entries.iter().cloned().collect();
+    let expected = reference.get_revision();
+
+    for iteration in 0..200 {
+        test_shuffle(&mut entries, iteration);
+
+        // Start small so that the map is resized several times while growing,
+        // and insert some temporary entries to force extra rehashing
+        let mut map: HashMap<i32, i32> = HashMap::with_capacity(1);
+        for (n, (k, v)) in entries.iter().enumerate() {
+            map.insert(*k, *v);
+            if n % 50 == 0 {
+                for t in 0..100 {
+                    map.insert(10_000 + t, t);
+                }
+                for t in 0..100 {
+                    map.remove(&(10_000 + t));
+                }
+            }
+        }
+        if iteration % 2 == 0 {
+            map.shrink_to_fit();
+        }
+
+        assert_eq!(map.len(), reference.len());
+        assert_eq!(map.get_revision(), expected);
+    }
+}
+
+#[test]
+fn revised_eq_exact() {
+    let a = Revised::new(TestCollidingItem { id: 1, revision: 7 });
+    let b = Revised::new(TestCollidingItem { id: 1, revision: 7 });
+    assert!(a.eq_exact(&b));
+
+    // Different values with a simulated collision
+    let c = Revised::new(TestCollidingItem { id: 2, revision: 7 });
+    assert_eq!(a.get_revision(), c.get_revision());
+    assert!(!a.eq_exact(&c));
+
+    // Different revisions are rejected without comparing values
+    let d = Revised::new(TestCollidingItem { id: 1, revision: 8 });
+    assert!(!a.eq_exact(&d));
+}
+
+#[test]
+fn revision_sink_matches_vec() {
+    let mut sink = RevisionSink::new();
+    assert_eq!(sink.clone().finish(), Vec::<TestInt>::new().get_revision());
+
+    let mut buffered = Vec::new();
+    for i in (0..1000).map(|i| (i * 37) % 101) {
+        sink.push(&TestInt(i));
+        buffered.push(TestInt(i));
+    }
+    assert_eq!(sink.len(), 1000);
+    assert_eq!(sink.finish(), buffered.get_revision());
+}
+
+struct TestFieldsChecked {
+    a: i32,
+    b: TestInt,
+}
+
+assert_revisable_fields!(TestFieldsChecked { a, b });
+
+impl Revisable for TestFieldsChecked {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_revisable(&self.a);
+        hasher.write_revisable(&self.b);
+        hasher.into_revision()
+    }
+}
+
+#[test]
+fn assert_revisable_fields_allows_exact_fields() {
+    let x = TestFieldsChecked {
+        a: 1,
+        b: TestInt(2),
+    };
+    assert_eq!(x.get_revision(), (1, TestInt(2)).get_revision());
+}
+
+#[test]
+fn merkle_first_divergence() {
+    let a = MerkleRevised::new((0..10).map(TestInt).collect());
+    let mut b = MerkleRevised::new((0..10).map(TestInt).collect());
+    assert_eq!(a.get_revision(), b.get_revision());
+    assert_eq!(a.first_divergence(&b), None);
+
+    b.set(6, TestInt(60));
+    assert_ne!(a.get_revision(), b.get_revision());
+
+    // The path to leaf 6 spells out its index in binary
+    let path = a.first_divergence(&b).unwrap();
+    assert_eq!(path, vec![0, 1, 1, 0]);
+    assert_eq!(b.node_revision(&path), TestInt(60).get_revision());
+
+    // Siblings along the path are unaffected
+    assert_eq!(a.node_revision(&[1]), b.node_revision(&[1]));
+    assert_eq!(a.node_revision(&[0, 0]), b.node_revision(&[0, 0]));
+    assert_ne!(a.node_revision(&[0, 1]), b.node_revision(&[0, 1]));
+
+    // The root only depends on the flattened leaves
+    let mut leaves: Vec<TestInt> = (0..10).map(TestInt).collect();
+    leaves[6] = TestInt(60);
+    assert_eq!(MerkleRevised::new(leaves).get_revision(), b.get_revision());
+    b.set(6, TestInt(6));
+    assert_eq!(a.first_divergence(&b), None);
+}
+
+#[test]
+fn property_fan_out() {
+    let upstream_calls = Cell::new(0);
+    let downstream_calls = Cell::new(0);
+    let mut upstream: RevisedProperty<Vec<i32>> = RevisedProperty::new();
+    let mut downstreams: Vec<RevisedProperty<i32>> =
+        (0..3).map(|_| RevisedProperty::new()).collect();
+
+    let expand = |n: i32| {
+        upstream_calls.set(upstream_calls.get() + 1);
+        (0..n).collect::<Vec<i32>>()
+    };
+    let fns = [
+        |v: &Vec<i32>| v.iter().sum::<i32>(),
+        |v: &Vec<i32>| v.len() as i32,
+        |v: &Vec<i32>| v.iter().max().copied().unwrap_or(0),
+    ];
+
+    for frame in 0..4 {
+        let n = if frame < 2 { 5 } else { 10 };
+        upstream.refresh1(expand, n);
+        for (downstream, f) in downstreams.iter_mut().zip(fns) {
+            downstream.refresh_from(
+                |v: &Vec<i32>| {
+                    downstream_calls.set(downstream_calls.get() + 1);
+                    f(v)
+                },
+                &upstream,
+            );
+        }
+    }
+
+    // Each input was computed once upstream and once per downstream
+    assert_eq!(upstream_calls.get(), 2);
+    assert_eq!(downstream_calls.get(), 6);
+    assert_eq!(upstream.cached_revision(), Some(10.get_revision()));
+    let outputs: Vec<i32> = downstreams
+        .iter()
+        .map(|d| *d.get_cached().unwrap())
+        .collect();
+    assert_eq!(outputs, vec![45, 10, 9]);
+}
+
+#[test]
+fn property_refresh_from_empty_upstream() {
+    let double = |v: &i32| v * 2;
+    let mut upstream: RevisedProperty<i32> = RevisedProperty::new();
+    let mut downstream: RevisedProperty<i32> = RevisedProperty::new();
+
+    // Never refreshed upstream: nothing happens
+    downstream.refresh_from(double, &upstream);
+    assert_eq!(downstream.get_cached(), None);
+    assert_eq!(downstream.cached_revision(), None);
+
+    upstream.refresh1(|x: i32| x + 1, 2);
+    downstream.refresh_from(double, &upstream);
+    assert_eq!(downstream.get_cached(), Some(&6));
+    let r0 = downstream.cached_revision();
+
+    // Invalidated upstream: the downstream keeps its cached value
+    upstream.invalidate();
+    downstream.refresh_from(|_: &i32| -> i32 { unreachable!() }, &upstream);
+    assert_eq!(downstream.get_cached(), Some(&6));
+    assert_eq!(downstream.cached_revision(), r0);
+}
+
+#[test]
+fn total_ord_float() {
+    assert_ne!(
+        TotalOrdFloat(0.0_f64).get_revision(),
+        TotalOrdFloat(-0.0_f64).get_revision()
+    );
+    assert_ne!(
+        TotalOrdFloat(0.0_f32).get_revision(),
+        TotalOrdFloat(-0.0_f32).get_revision()
+    );
+    assert_eq!(
+        TotalOrdFloat(1.5_f64).get_revision(),
+        TotalOrdFloat(1.5_f64).get_revision()
+    );
+
+    // NaNs with different sign bits or payloads are distinct too
+    let quiet = f64::NAN;
+    let negative = -f64::NAN;
+    let payload = f64::from_bits(f64::NAN.to_bits() | 1);
+    assert_ne!(
+        TotalOrdFloat(quiet).get_revision(),
+        TotalOrdFloat(negative).get_revision()
+    );
+    assert_ne!(
+        TotalOrdFloat(quiet).get_revision(),
+        TotalOrdFloat(payload).get_revision()
+    );
+    assert_eq!(
+        TotalOrdFloat(quiet).get_revision(),
+        TotalOrdFloat(quiet).get_revision()
+    );
+}
+
+#[test]
+fn row_revision_builder() {
+    let row = |name: Option<&str>, score: Option<f64>| {
+        let mut builder = RowRevisionBuilder::new();
+        builder.add_i64(Some(1)).add_text(name).add_f64(score);
+        builder.finish()
+    };
+    assert_eq!(row(Some("ada"), Some(1.5)), row(Some("ada"), Some(1.5)));
+    assert_ne!(row(Some("ada"), Some(1.5)), row(Some("bob"), Some(1.5)));
+    assert_eq!(row(Some("ada"), Some(0.0)), row(Some("ada"), Some(-0.0)));
+
+    // NULL is distinct from an empty string
+    assert_ne!(row(None, None), row(Some(""), None));
+
+    // NULLs of different types are distinct
+    let mut null_int = RowRevisionBuilder::new();
+    null_int.add_i64(None);
+    let mut null_text = RowRevisionBuilder::new();
+    null_text.add_text(None);
+    assert_ne!(null_int.finish(), null_text.finish());
+
+    // Positional columns depend on order
+    let mut ab = RowRevisionBuilder::new();
+    ab.add_i64(Some(1)).add_i64(Some(2));
+    let mut ba = RowRevisionBuilder::new();
+    ba.add_i64(Some(2)).add_i64(Some(1));
+    assert_ne!(ab.finish(), ba.finish());
+
+    // Named columns don't
+    let mut ab = RowRevisionBuilder::new();
+    ab.name("a")
+        .add_i64(Some(1))
+        .name("b")
+        .add_blob(Some(b"xy"));
+    let mut ba = RowRevisionBuilder::new();
+    ba.name("b")
+        .add_blob(Some(b"xy"))
+        .name("a")
+        .add_i64(Some(1));
+    assert_eq!(ab.finish(), ba.finish());
+
+    // But names do matter
+    let mut swapped = RowRevisionBuilder::new();
+    swapped
+        .name("b")
+        .add_i64(Some(1))
+        .name("a")
+        .add_blob(Some(b"xy"));
+    assert_ne!(ab.finish(), swapped.finish());
+}
+
+#[test]
+#[cfg_attr(
+    all(feature = "debug-purity-checks", debug_assertions),
+    ignore = "counts revision computations, which purity checks double"
+)]
+fn revised_edit() {
+    let counter = Rc::new(Cell::new(0));
+    let mut x = Revised::new(CountedInt::new(0, &counter));
+    let r0 = x.get_revision();
+    assert_eq!(counter.get(), 1);
+
+    let (old, r1) = x.edit(|v| {
+        let old = v.value;
+        for i in 0..10 {
+            v.value += i;
+        }
+        old
+    });
+    assert_eq!(old, 0);
+    assert_eq!(x.as_ref().value, 45);
+    assert_eq!(counter.get(), 2);
+    assert_ne!(r0, r1);
+
+    // The fresh revision was cached
+    assert_eq!(x.get_revision(), r1);
+    assert_eq!(counter.get(), 2);
+}
+
+#[cfg(feature = "glam")]
+#[test]
+fn glam_revisions() {
+    use glam::{DVec3, IVec2, Mat4, Quat, Vec3};
+
+    assert_eq!(
+        Vec3::new(1.0, 2.0, 3.0).get_revision(),
+        Vec3::new(1.0, 2.0, 3.0).get_revision()
+    );
+    assert_ne!(
+        Vec3::new(1.0, 2.0, 3.0).get_revision(),
+        Vec3::new(1.0, 2.5, 3.0).get_revision()
+    );
+    assert_eq!(
+        Vec3::new(0.0, -0.0, 1.0).get_revision(),
+        Vec3::new(-0.0, 0.0, 1.0).get_revision()
+    );
+    assert_eq!(
+        DVec3::new(f64::NAN, -0.0, 1.0).get_revision(),
+        DVec3::new(-f64::NAN, 0.0, 1.0).get_revision()
+    );
+    assert_ne!(
+        IVec2::new(1, 2).get_revision(),
+        IVec2::new(2, 1).get_revision()
+    );
+
+    let a = Mat4::from_rotation_translation(Quat::IDENTITY, Vec3::new(1.0, 0.0, 0.0));
+    let b = Mat4::from_rotation_translation(Quat::IDENTITY, Vec3::new(0.0, 1.0, 0.0));
+    assert_ne!(a.get_revision(), b.get_revision());
+    assert_eq!(a.get_revision(), a.get_revision());
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn nalgebra_revisions() {
+    use nalgebra::{DMatrix, Matrix2, Point3, Vector3};
+
+    assert_eq!(
+        Vector3::new(1.0_f32, 2.0, 3.0).get_revision(),
+        Vector3::new(1.0_f32, 2.0, 3.0).get_revision()
+    );
+    assert_ne!(
+        Vector3::new(1.0_f32, 2.0, 3.0).get_revision(),
+        Vector3::new(1.0_f32, 2.0, 4.0).get_revision()
+    );
+    assert_eq!(
+        Vector3::new(0.0_f64, -0.0, 1.0).get_revision(),
+        Vector3::new(-0.0_f64, 0.0, 1.0).get_revision()
+    );
+    assert_eq!(
+        Point3::new(1.0_f64, 2.0, 3.0).get_revision(),
+        Vector3::new(1.0_f64, 2.0, 3.0).get_revision()
+    );
+
+    // Dynamic and static matrices with equal contents hash equal, but the
+    // dimensions are part of the revision
+    let m = Matrix2::new(1.0_f64, 2.0, 3.0, 4.0);
+    let d = DMatrix::from_column_slice(2, 2, &[1.0_f64, 3.0, 2.0, 4.0]);
+    assert_eq!(m.get_revision(), d.get_revision());
+    let row = DMatrix::from_column_slice(1, 4, &[1.0_f64, 3.0, 2.0, 4.0]);
+    assert_ne!(row.get_revision(), d.get_revision());
+}
+
+#[test]
+fn ordered_set_revision() {
+    let queue: VecDeque<TestInt> = (1..=4).map(TestInt).collect();
+    let same: VecDeque<TestInt> = (1..=4).map(TestInt).collect();
+    assert_eq!(
+        revision_of_ordered_set(&queue),
+        revision_of_ordered_set(&same)
+    );
+
+    // Reprioritizing two tasks changes the revision but not the membership
+    let mut reordered = same;
+    reordered.swap(1, 2);
+    assert_ne!(
+        revision_of_ordered_set(&queue),
+        revision_of_ordered_set(&reordered)
+    );
+    let set = |q: &VecDeque<TestInt>| q.iter().map(|t| t.0).collect::<HashSet<i32>>();
+    assert_eq!(set(&queue), set(&reordered));
+
+    let as_vec: Vec<TestInt> = (1..=4).map(TestInt).collect();
+    assert_eq!(revision_of_ordered_set(&queue), as_vec.get_revision());
+}
+
+#[cfg(feature = "derive")]
+#[derive(crate::Revisable)]
+#[revise(field_revisions)]
+struct TestReplicated {
+    position: (i32, i32),
+    health: i32,
+    #[revise(skip)]
+    interpolation: f32,
+    name: Vec<u8>,
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn diff_fields_reports_changed_field() {
+    let mut x = TestReplicated {
+        position: (1, 2),
+        health: 100,
+        interpolation: 0.0,
+        name: b"orc".to_vec(),
+    };
+    let before = x.field_revisions();
+    assert!(diff_fields(&before, &x.field_revisions()).is_empty());
+
+    // Skipped fields aren't listed, and the others are named in order
+    let names: Vec<&str> = before.iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, vec!["position", "health", "name"]);
+    x.interpolation = 0.5;
+    assert!(diff_fields(&before, &x.field_revisions()).is_empty());
+
+    x.health -= 10;
+    assert_eq!(diff_fields(&before, &x.field_revisions()), vec!["health"]);
+
+    x.position.1 = 3;
+    assert_eq!(
+        diff_fields(&before, &x.field_revisions()),
+        vec!["position", "health"]
+    );
+
+    // Fields missing from the previous snapshot count as changed
+    assert_eq!(diff_fields(&[], &x.field_revisions()).len(), 3);
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_structs_and_enums() {
+    #[derive(crate::Revisable)]
+    struct Named {
+        a: TestInt,
+        b: u8,
+        #[revise(skip)]
+        cache: Cell<usize>,
+    }
+
+    #[derive(crate::Revisable)]
+    struct Tuple(TestInt, u8);
+
+    #[derive(crate::Revisable)]
+    struct Generic<T> {
+        items: Vec<T>,
+    }
+
+    #[derive(crate::Revisable)]
+    enum Shape {
+        Empty,
+        Circle(i32),
+        Rect { w: i32, h: i32 },
+    }
+
+    let named = Named {
+        a: TestInt(1),
+        b: 2,
+        cache: Cell::new(0),
+    };
+    let mut hasher = RevisionHasher::new();
+    hasher.write_revisable(&TestInt(1));
+    hasher.write_revisable(&2_u8);
+    let expected = hasher.into_revision();
+    assert_eq!(named.get_revision(), expected);
+    assert_eq!(Tuple(TestInt(1), 2).get_revision(), expected);
+
+    // Skipped fields don't affect the revision
+    named.cache.set(10);
+    assert_eq!(named.get_revision(), expected);
+
+    let generic = Generic {
+        items: vec![TestInt(1), TestInt(2)],
+    };
+    let mut hasher = RevisionHasher::new();
+    hasher.write_revisable(&vec![TestInt(1), TestInt(2)]);
+    assert_eq!(generic.get_revision(), hasher.into_revision());
+
+    // Enums hash the variant index first, with a fast path for unit variants
+    assert_eq!(Shape::Empty.get_revision(), RevisionHash::unit_variant(0));
+    let mut hasher = RevisionHasher::new();
+    hasher.write_u32(1);
+    hasher.write_revisable(&5_i32);
+    assert_eq!(Shape::Circle(5).get_revision(), hasher.into_revision());
+    assert_ne!(
+        Shape::Rect { w: 1, h: 2 }.get_revision(),
+        Shape::Rect { w: 2, h: 1 }.get_revision()
+    );
+    assert_ne!(
+        Shape::Circle(0).get_revision(),
+        Shape::Rect { w: 0, h: 0 }.get_revision()
+    );
+}
+
+#[test]
+fn channel_revised_notifications() {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut x = ChannelRevised::new(TestInt(1), sender);
+    assert!(receiver.try_recv().is_err());
+
+    // The first read reports the initial revision
+    let r1 = x.get_revision();
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![r1]);
+
+    // Reading again without mutating reports nothing
+    x.get_revision();
+    assert!(receiver.try_recv().is_err());
+
+    // Several mutations before a read are reported once
+    x.0 = 2;
+    x.0 = 3;
+    let r3 = x.get_revision();
+    x.get_revision();
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![r3]);
+
+    // A mutation which doesn't change the revision isn't reported
+    x.0 = 3;
+    assert_eq!(x.get_revision(), r3);
+    assert!(receiver.try_recv().is_err());
+
+    // Changing back is reported, even though the revision was seen before
+    x.0 = 1;
+    assert_eq!(x.get_revision(), r1);
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![r1]);
+}
+
+struct TestConfig {
+    name: TestInt,
+    limit: Option<i32>,
+}
+
+impl Revisable for TestConfig {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_revisable(&self.name);
+        hasher.write_revisable(&self.limit);
+        hasher.into_revision()
+    }
+}
+
+#[test]
+fn option_revisions() {
+    let none: Option<i32> = None;
+    assert_ne!(none.get_revision(), Some(0).get_revision());
+    assert_ne!(none.get_revision(), RevisionHash::new(0));
+    assert_eq!(none.get_revision(), None::<i32>.get_revision());
+
+    // Some(x) doesn't collide with the bare revision of x
+    assert_ne!(Some(0).get_revision(), 0.get_revision());
+    assert_ne!(Some(TestInt(5)).get_revision(), TestInt(5).get_revision());
+
+    // Toggling an optional field changes the parent's revision
+    let mut config = TestConfig {
+        name: TestInt(1),
+        limit: None,
+    };
+    let r_none = config.get_revision();
+    config.limit = Some(0);
+    let r_some = config.get_revision();
+    assert_ne!(r_none, r_some);
+    config.limit = None;
+    assert_eq!(config.get_revision(), r_none);
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn json_value_revisions() {
+    use serde_json::json;
+
+    let a = json!({"name": "x", "tags": [1, 2, 3], "nested": {"a": null, "b": true}});
+    let b = json!({"nested": {"b": true, "a": null}, "tags": [1, 2, 3], "name": "x"});
+    assert_eq!(a.get_revision(), b.get_revision());
+
+    // Arrays are ordered
+    assert_ne!(
+        json!([1, 2, 3]).get_revision(),
+        json!([3, 2, 1]).get_revision()
+    );
+
+    // Values of different kinds don't collide
+    assert_ne!(json!(null).get_revision(), json!(false).get_revision());
+    assert_ne!(json!("1").get_revision(), json!(1).get_revision());
+    assert_ne!(json!([]).get_revision(), json!({}).get_revision());
+    assert_ne!(
+        json!({"a": 1}).get_revision(),
+        json!({"a": 2}).get_revision()
+    );
+    assert_ne!(
+        json!({"a": 1}).get_revision(),
+        json!({"b": 1}).get_revision()
+    );
+    assert_eq!(json!(-0.0).get_revision(), json!(0.0).get_revision());
+}
+
+#[test]
+fn result_revisions() {
+    let ok: Result<TestInt, TestInt> = Ok(TestInt(3));
+    let err: Result<TestInt, TestInt> = Err(TestInt(3));
+    assert_ne!(ok.get_revision(), err.get_revision());
+    assert_ne!(ok.get_revision(), TestInt(3).get_revision());
+    assert_eq!(
+        ok.get_revision(),
+        Ok::<_, TestInt>(TestInt(3)).get_revision()
+    );
+    assert_ne!(
+        ok.get_revision(),
+        Ok::<_, TestInt>(TestInt(4)).get_revision()
+    );
+}
+
+#[test]
+fn primitive_revisions() {
+    assert_eq!(1.5_f64.get_revision(), 1.5_f64.get_revision());
+    assert_ne!(1.5_f64.get_revision(), 2.5_f64.get_revision());
+    assert_ne!(1.5_f32.get_revision(), 2.5_f32.get_revision());
+    assert_ne!('a'.get_revision(), 'b'.get_revision());
+    assert_eq!('a'.get_revision(), ('a' as u32).get_revision());
+    assert_eq!(true.get_revision(), 1_u8.get_revision());
+    assert_ne!(true.get_revision(), false.get_revision());
+    assert_eq!(().get_revision(), RevisionHasher::new().into_revision());
+    assert_ne!(().get_revision(), 0_u8.get_revision());
+}
+
+#[test]
+fn canonical_float_revisions() {
+    let nans = [
+        f64::NAN,
+        -f64::NAN,
+        f64::from_bits(0x7ff0_0000_0000_0001),
+        f64::from_bits(0x7ff8_dead_beef_0000),
+        f64::from_bits(0xfff0_0000_0000_0100),
+    ];
+    for nan in nans {
+        assert!(nan.is_nan());
+        assert_eq!(nan.get_revision(), f64::NAN.get_revision());
+    }
+    let nans_f32 = [f32::NAN, -f32::NAN, f32::from_bits(0x7f80_0001)];
+    for nan in nans_f32 {
+        assert_eq!(nan.get_revision(), f32::NAN.get_revision());
+    }
+
+    assert_eq!(0.0_f64.get_revision(), (-0.0_f64).get_revision());
+    assert_eq!(0.0_f32.get_revision(), (-0.0_f32).get_revision());
+    assert_ne!(f64::NAN.get_revision(), f64::INFINITY.get_revision());
+    assert_ne!(0.0_f64.get_revision(), f64::MIN_POSITIVE.get_revision());
+}
+
+#[test]
+fn string_revisions() {
+    assert_ne!(("ab", "").get_revision(), ("a", "b").get_revision());
+    assert_ne!(("", "ab").get_revision(), ("a", "b").get_revision());
+    assert_eq!("abc".get_revision(), String::from("abc").get_revision());
+    assert_ne!("abc".get_revision(), "abd".get_revision());
+    assert_ne!("".get_revision(), ().get_revision());
+}
+
+struct TestTree {
+    value: i32,
+    left: Option<Box<TestTree>>,
+    right: Option<Box<TestTree>>,
+}
+
+impl Revisable for TestTree {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_i32(self.value);
+        hasher.write_revisable(&self.left);
+        hasher.write_revisable(&self.right);
+        hasher.into_revision()
+    }
+}
+
+#[test]
+fn smart_pointer_revisions() {
+    fn tree(depth: i32) -> TestTree {
+        let child = || (depth > 0).then(|| Box::new(tree(depth - 1)));
+        TestTree {
+            value: depth,
+            left: child(),
+            right: child(),
+        }
+    }
+    let mut root = tree(4);
+    let r0 = root.get_revision();
+    let mut node = &mut root;
+    while let Some(child) = node.right.as_mut() {
+        node = child;
+    }
+    node.value = 100;
+    assert_ne!(root.get_revision(), r0);
+
+    // Smart pointers are transparent
+    assert_eq!(
+        Rc::new(TestInt(1)).get_revision(),
+        TestInt(1).get_revision()
+    );
+    assert_eq!(
+        std::sync::Arc::new(TestInt(1)).get_revision(),
+        TestInt(1).get_revision()
+    );
+    let unsized_rc: Rc<[TestInt]> = Rc::new([TestInt(1), TestInt(2)]);
+    let unsized_box: Box<[TestInt]> = Box::new([TestInt(1), TestInt(2)]);
+    assert_eq!(unsized_rc.get_revision(), unsized_box.get_revision());
+}
+
+#[test]
+fn dyn_revisable_collections() {
+    fn assert_revisable<T: Revisable + ?Sized>(_: &T) {}
+
+    let mut items: Vec<Box<dyn Revisable>> = vec![Box::new(TestInt(1)), Box::new("one")];
+    assert_revisable::<dyn Revisable>(&*items[0]);
+    let r0 = items.get_revision();
+    items.swap(0, 1);
+    assert_ne!(items.get_revision(), r0);
+    items.swap(0, 1);
+    assert_eq!(items.get_revision(), r0);
+    items[1] = Box::new(1.0_f64);
+    assert_ne!(items.get_revision(), r0);
+
+    let shared: Vec<Box<dyn Revisable + Send + Sync>> = vec![Box::new(TestInt(1)), Box::new("one")];
+    assert_revisable::<dyn Revisable + Send + Sync>(&*shared[0]);
+    assert_eq!(shared.get_revision(), r0);
+}
+
+#[test]
+fn hashmap_insertion_order_independent() {
+    let mut forward: HashMap<i32, String> = HashMap::new();
+    for i in 0..100 {
+        forward.insert(i, i.to_string());
+    }
+    let mut backward: HashMap<i32, String> = HashMap::with_capacity(500);
+    for i in (0..100).rev() {
+        backward.insert(i, i.to_string());
+    }
+    assert_eq!(forward.get_revision(), backward.get_revision());
+    assert_eq!(Revisable::get_revision(&&forward), backward.get_revision());
+
+    let btree: BTreeMap<i32, String> = backward.into_iter().collect();
+    assert_eq!(forward.get_revision(), btree.get_revision());
+}
+
+#[test]
+fn btreemap_matches_hashmap() {
+    let entries = [(3, "c"), (1, "a"), (2, "b")];
+    let btree: BTreeMap<i32, &str> = entries.iter().cloned().collect();
+    let hash: HashMap<i32, &str> = entries.iter().cloned().collect();
+    assert_eq!(btree.get_revision(), hash.get_revision());
+
+    let mut changed = btree.clone();
+    changed.insert(2, "z");
+    assert_ne!(changed.get_revision(), btree.get_revision());
+    assert_ne!(
+        BTreeMap::<i32, i32>::new().get_revision(),
+        BTreeMap::from([(0, 0)]).get_revision()
+    );
+}
+
+#[test]
+fn set_revisions() {
+    let empty: HashSet<i32> = HashSet::new();
+    assert_eq!(empty.get_revision(), HashSet::<i32>::new().get_revision());
+    assert_eq!(empty.get_revision(), BTreeSet::<i32>::new().get_revision());
+
+    let small: HashSet<i32> = [1, 2].into_iter().collect();
+    let large: HashSet<i32> = [1, 2, 3].into_iter().collect();
+    assert_ne!(small.get_revision(), large.get_revision());
+    assert_ne!(small.get_revision(), empty.get_revision());
+
+    let reversed: BTreeSet<i32> = [3, 2, 1].into_iter().collect();
+    assert_eq!(reversed.get_revision(), large.get_revision());
+
+    // A single element is tagged, so it doesn't collide with the element
+    let single: BTreeSet<i32> = [7].into_iter().collect();
+    assert_ne!(single.get_revision(), 7.get_revision());
+}
+
+#[test]
+fn array_revisions() {
+    let a = [TestInt(1), TestInt(2), TestInt(3)];
+    let b = [TestInt(1), TestInt(2), TestInt(3)];
+    let c = [TestInt(1), TestInt(2), TestInt(4)];
+    assert_eq!(a.get_revision(), b.get_revision());
+    assert_ne!(a.get_revision(), c.get_revision());
+
+    let s: &[TestInt] = &a;
+    assert_eq!(a.get_revision(), s.get_revision());
+    assert_eq!(
+        a.get_revision(),
+        vec![TestInt(1), TestInt(2), TestInt(3)].get_revision()
+    );
+
+    let empty: [TestInt; 0] = [];
+    assert_eq!(empty.get_revision(), Vec::<TestInt>::new().get_revision());
+}
+
+#[test]
+fn large_tuple_revisions() {
+    let a = (1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12);
+    let b = (1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 13);
+    assert_eq!(a.get_revision(), a.clone().get_revision());
+    assert_ne!(a.get_revision(), b.get_revision());
+
+    let mut hasher = RevisionHasher::new();
+    for x in [1, 2, 3, 4, 5, 6] {
+        hasher.write_revision(x.get_revision());
+    }
+    assert_eq!((1, 2, 3, 4, 5, 6).get_revision(), hasher.into_revision());
+}
+
+#[test]
+fn revised_property_many_args() {
+    let calls = Cell::new(0);
+    let f = |a: i32, b: i32, c: i32, d: i32, e: i32| {
+        calls.set(calls.get() + 1);
+        a + b + c + d + e
+    };
+
+    let mut property = RevisedProperty::new();
+    property.refresh5(f, 1, 2, 3, 4, 5);
+    assert_eq!(calls.get(), 1);
+    assert_eq!(property.get_cached(), Some(&15));
+
+    // All five arguments unchanged: skipped
+    property.refresh5(f, 1, 2, 3, 4, 5);
+    assert_eq!(calls.get(), 1);
+
+    property.refresh5(f, 1, 2, 3, 4, 6);
+    assert_eq!(calls.get(), 2);
+    assert_eq!(property.get_cached(), Some(&16));
+
+    let calls = Cell::new(0);
+    let g = |a: i32, b: i32, c: i32, d: i32, e: i32, f: i32, g: i32, h: i32| {
+        calls.set(calls.get() + 1);
+        a + b + c + d + e + f + g + h
+    };
+
+    let mut property = RevisedProperty::new();
+    property.refresh8(g, 1, 2, 3, 4, 5, 6, 7, 8);
+    property.refresh8(g, 1, 2, 3, 4, 5, 6, 7, 8);
+    assert_eq!(calls.get(), 1);
+    assert_eq!(property.get_cached(), Some(&36));
+
+    property.refresh8(g, 1, 2, 3, 4, 5, 6, 7, 9);
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn revised_property_refresh_tuple() {
+    let calls = Cell::new(0);
+    let f = |(a, b, c, d, e): &(i32, i32, i32, i32, TestInt)| {
+        calls.set(calls.get() + 1);
+        a + b + c + d + e.0
+    };
+
+    let mut property = RevisedProperty::new();
+    property.refresh(f, (1, 2, 3, 4, TestInt(5)));
+    assert_eq!(calls.get(), 1);
+    assert_eq!(property.get_cached(), Some(&15));
+
+    property.refresh(f, (1, 2, 3, 4, TestInt(5)));
+    assert_eq!(calls.get(), 1);
+
+    property.refresh(f, (1, 2, 3, 4, TestInt(6)));
+    assert_eq!(calls.get(), 2);
+    assert_eq!(property.get_cached(), Some(&16));
+
+    // The cache key is the revision of the arguments, so refresh can pick
+    // up where refresh5 left off
+    property.refresh5(
+        |a, b, c, d, e: TestInt| a + b + c + d + e.0,
+        1,
+        2,
+        3,
+        4,
+        TestInt(6),
+    );
+    property.refresh(f, (1, 2, 3, 4, TestInt(6)));
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn custom_underlying_hasher() {
+    use std::collections::hash_map::DefaultHasher;
+
+    let mut default = RevisionHasher::new();
+    default.write_revisable(&TestInt(1));
+    let mut sea = RevisionHasher::with_hasher(seahash::SeaHasher::new());
+    sea.write_revisable(&TestInt(1));
+    assert_eq!(default.into_revision(), sea.into_revision());
+
+    let revision = |x: i32| {
+        let mut hasher = RevisionHasher::with_hasher(DefaultHasher::new());
+        hasher.write_revisable(&TestInt(x));
+        hasher.into_revision()
+    };
+    assert_eq!(revision(1), revision(1));
+    assert_ne!(revision(1), revision(2));
+
+    /// Hasher which keeps the bytes written to it, and whose hash
+    /// depends on their order
+    #[derive(Default)]
+    struct BytesHasher(Vec<u8>);
+
+    impl Hasher for BytesHasher {
+        fn finish(&self) -> u64 {
+            self.0
+                .iter()
+                .fold(0, |h: u64, b| h.wrapping_mul(257).wrapping_add(*b as u64))
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.extend_from_slice(bytes);
+        }
+    }
+
+    // Nested revisions reach a custom hasher as little-endian bytes too
+    let mut bytes = RevisionHasher::with_hasher(BytesHasher::default());
+    bytes.write_revision(RevisionHash::new(0x0807_0605_0403_0201));
+    let mut expected = RevisionHasher::with_hasher(BytesHasher::default());
+    expected.write(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(bytes.finish(), expected.finish());
+}
+
+#[test]
+fn sync_revised_threads() {
+    let value = Arc::new(SyncRevised::new(vec![1, 2, 3]));
+    let expected = vec![1, 2, 3].get_revision();
+
+    let revisions: Vec<RevisionHash> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..8)
+            .map(|_| scope.spawn(|| value.get_revision()))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    assert!(revisions.iter().all(|r| *r == expected));
+
+    let mut value = Arc::try_unwrap(value).ok().unwrap();
+    value.push(4);
+    assert_eq!(value.get_revision(), vec![1, 2, 3, 4].get_revision());
+    assert_eq!(
+        value.get_revision(),
+        Revised::new(vec![1, 2, 3, 4]).get_revision()
+    );
+}
+
+/// A map key whose revision only depends on its first field, so that
+/// distinct keys can have colliding revisions
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct TestCollidingKey(i32, i32);
+
+impl Revisable for TestCollidingKey {
+    fn get_revision(&self) -> RevisionHash {
+        self.0.get_revision()
+    }
+}
+
+impl Revisable128 for TestCollidingKey {
+    fn get_revision128(&self) -> RevisionHash128 {
+        self.0.get_revision128()
+    }
+}
+
+#[test]
+fn map_revision_entries_dont_cancel() {
+    // Each map has two distinct entries with the same entry hash. These
+    // used to cancel out when entries were combined with xor, making both
+    // maps hash the same as any other map of two such entries.
+    let a: BTreeMap<TestCollidingKey, i32> =
+        [(TestCollidingKey(1, 0), 5), (TestCollidingKey(1, 1), 5)]
+            .into_iter()
+            .collect();
+    let b: BTreeMap<TestCollidingKey, i32> =
+        [(TestCollidingKey(2, 0), 7), (TestCollidingKey(2, 1), 7)]
+            .into_iter()
+            .collect();
+    assert_ne!(a.get_revision(), b.get_revision());
+}
+
+#[test]
+fn map_revision_key_value_swap() {
+    let a: HashMap<i32, i32> = [(1, 2)].into_iter().collect();
+    let b: HashMap<i32, i32> = [(2, 1)].into_iter().collect();
+    assert_ne!(a.get_revision(), b.get_revision());
+
+    let c: HashMap<i32, i32> = [(1, 2), (3, 4)].into_iter().collect();
+    let d: HashMap<i32, i32> = [(2, 1), (4, 3)].into_iter().collect();
+    let e: HashMap<i32, i32> = [(1, 4), (3, 2)].into_iter().collect();
+    assert_ne!(c.get_revision(), d.get_revision());
+    assert_ne!(c.get_revision(), e.get_revision());
+}
+
+#[test]
+fn vec_deque_revision() {
+    let mut queue: VecDeque<TestInt> = (1..=4).map(TestInt).collect();
+    let as_vec: Vec<TestInt> = (1..=4).map(TestInt).collect();
+    assert_eq!(queue.get_revision(), as_vec.get_revision());
+
+    // Same elements in a different order
+    let before = queue.get_revision();
+    queue.rotate_left(1);
+    assert_ne!(queue.get_revision(), before);
+
+    queue.rotate_right(1);
+    assert_eq!(queue.get_revision(), before);
+
+    // Wrapping around the ring buffer doesn't affect the revision
+    let mut wrapped: VecDeque<TestInt> = VecDeque::with_capacity(4);
+    wrapped.push_back(TestInt(3));
+    wrapped.push_back(TestInt(4));
+    wrapped.push_front(TestInt(2));
+    wrapped.push_front(TestInt(1));
+    assert_eq!(wrapped.get_revision(), before);
+}
+
+#[test]
+fn revised_peek_revision() {
+    let mut x = Revised::new(TestInt(1));
+    assert!(x.is_dirty());
+    assert_eq!(x.peek_revision(), None);
+
+    // Peeking doesn't populate the cache
+    assert!(x.is_dirty());
+
+    let r = x.get_revision();
+    assert!(!x.is_dirty());
+    assert_eq!(x.peek_revision(), Some(r));
+
+    // Reading doesn't invalidate
+    assert_eq!(x.0, 1);
+    assert_eq!(x.peek_revision(), Some(r));
+
+    x.0 = 2;
+    assert!(x.is_dirty());
+    assert_eq!(x.peek_revision(), None);
+    assert_ne!(x.get_revision(), r);
+}
+
+#[test]
+fn revised_modify() {
+    let mut x = Revised::new(TestInt(1));
+    let r = x.get_revision();
+
+    // Writing the same value keeps the same cached revision
+    let old = x.modify(|v| std::mem::replace(&mut v.0, 1));
+    assert_eq!(old, 1);
+    assert_eq!(x.peek_revision(), Some(r));
+
+    // An actual change is cached too
+    x.modify(|v| v.0 = 2);
+    let r2 = x.peek_revision().unwrap();
+    assert_ne!(r2, r);
+    assert_eq!(r2, TestInt(2).get_revision());
+}
+
+#[test]
+fn revised_debug() {
+    let x = Revised::new(vec![1, 2]);
+    assert_eq!(
+        format!("{:?}", x),
+        "Revised { value: [1, 2], revision: None }"
+    );
+
+    // Printing doesn't compute the revision
+    assert!(x.is_dirty());
+
+    let r = x.get_revision();
+    assert_eq!(
+        format!("{:?}", x),
+        format!("Revised {{ value: [1, 2], revision: Some({:?}) }}", r)
+    );
+}
+
+#[test]
+fn revised_eq() {
+    let a = Revised::new(vec![TestInt(1), TestInt(2)]);
+    let b = Revised::new(vec![TestInt(1), TestInt(2)]);
+    let c = Revised::new(vec![TestInt(2), TestInt(1)]);
+    assert!(a == b);
+    assert!(a != c);
+
+    // Comparing computes and caches both revisions
+    assert!(!a.is_dirty());
+    assert!(!b.is_dirty());
+}
+
+#[test]
+fn revised_property_invalidate() {
+    let calls = Cell::new(0);
+    let f = |x: i32| {
+        calls.set(calls.get() + 1);
+        x * 2
+    };
+
+    let mut property = RevisedProperty::new();
+    property.refresh1(f, 3);
+    property.refresh1(f, 3);
+    assert_eq!(calls.get(), 1);
+
+    property.invalidate();
+    assert_eq!(property.get_cached(), Some(&6));
+    assert_eq!(property.cached_revision(), None);
+
+    // Unchanged arguments, but the function is called again
+    property.refresh1(f, 3);
+    assert_eq!(calls.get(), 2);
+    assert_eq!(property.cached_revision(), Some(3.get_revision()));
+
+    property.refresh1(f, 3);
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn revised_property_cached_revision() {
+    let mut property: RevisedProperty<i32> = RevisedProperty::new();
+    assert_eq!(property.cached_revision(), None);
+
+    property.refresh1(|x: i32| x * 2, 5);
+    assert_eq!(property.cached_revision(), Some(5.get_revision()));
+
+    // Multiple arguments are identified by the revision of their tuple
+    property.refresh2(|a: i32, b: i32| a + b, 1, 2);
+    assert_eq!(property.cached_revision(), Some((1, 2).get_revision()));
+}
+
+#[test]
+fn revised_property_get_or_refresh() {
+    let calls = Cell::new(0);
+    let f = |a: i32, b: i32| {
+        calls.set(calls.get() + 1);
+        a + b
+    };
+
+    let mut property = RevisedProperty::new();
+    assert_eq!(*property.get_or_refresh2(f, 1, 2), 3);
+    assert_eq!(*property.get_or_refresh2(f, 1, 2), 3);
+    assert_eq!(calls.get(), 1);
+    assert_eq!(*property.get_or_refresh2(f, 2, 2), 4);
+    assert_eq!(calls.get(), 2);
+
+    let mut single = RevisedProperty::new();
+    assert_eq!(*single.get_or_refresh1(|x: i32| x * 10, 4), 40);
+
+    let mut triple = RevisedProperty::new();
+    assert_eq!(
+        *triple.get_or_refresh3(|a: i32, b: i32, c: i32| a * b * c, 2, 3, 4),
+        24
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn revision_hash_serde_round_trip() {
+    let revision = vec![1, 2, 3].get_revision();
+    let saved = serde_json::to_string(&revision).unwrap();
+    assert_eq!(saved, revision.value().to_string());
+    let loaded: RevisionHash = serde_json::from_str(&saved).unwrap();
+    assert_eq!(loaded, revision);
+
+    let revised = Revised::new(vec![1, 2, 3]);
+    assert_eq!(revised.get_revision(), revision);
+    let saved = serde_json::to_string(&revised).unwrap();
+    assert_eq!(saved, "[1,2,3]");
+    let loaded: Revised<Vec<i32>> = serde_json::from_str(&saved).unwrap();
+    assert!(loaded.is_dirty());
+    assert_eq!(loaded.get_revision(), revision);
+}
+
+#[test]
+fn portable_revisions() {
+    // These must stay the same on all platforms, regardless of byte order
+    // or pointer width
+    assert_eq!(1234_u32.get_revision().value(), 0x15e6471479aae54e);
+    assert_eq!((-5_i64).get_revision().value(), 0x318fdb4f2a4197c4);
+    assert_eq!(vec![1_u8, 2, 3].get_revision().value(), 0xff1e6154d55811ec);
+    assert_eq!("hello".get_revision().value(), 0x15d326c26b722865);
+    assert_eq!(
+        (true, 'x', 2.5_f64).get_revision().value(),
+        0x6f87df17e4493b4b
+    );
+
+    // Integers are written as their little-endian bytes
+    let mut a = RevisionHasher::new();
+    a.write_u32(0x0403_0201);
+    let mut b = RevisionHasher::new();
+    b.write(&[1, 2, 3, 4]);
+    assert_eq!(a.into_revision(), b.into_revision());
+
+    // usize and isize are always written as 64 bits
+    let mut a = RevisionHasher::new();
+    a.write_usize(3);
+    a.write_isize(-1);
+    let mut b = RevisionHasher::new();
+    b.write_u64(3);
+    b.write_i64(-1);
+    assert_eq!(a.into_revision(), b.into_revision());
+}
+
+#[test]
+fn hasher_write_iter() {
+    let items = vec![TestInt(1), TestInt(2), TestInt(3)];
+
+    let mut hasher = RevisionHasher::new();
+    hasher.write_iter(&items);
+    assert_eq!(hasher.into_revision(), items.get_revision());
+
+    // Exact-size iterators write the length first, like [T]
+    let mut hasher = RevisionHasher::new();
+    hasher.write_iter((1..4).map(TestInt));
+    assert_eq!(hasher.into_revision(), items.get_revision());
+
+    // Iterators without a known length are counted as they go, and the
+    // count is written last
+    let mut hasher = RevisionHasher::new();
+    hasher.write_iter((1..10).filter(|x| x % 3 == 0).map(TestInt));
+    let mut counted = RevisionHasher::new();
+    for x in [3, 6, 9] {
+        counted.write_revisable(&TestInt(x));
+    }
+    counted.write_usize(3);
+    assert_eq!(hasher.into_revision(), counted.into_revision());
+
+    let mut hasher = RevisionHasher::new();
+    hasher.write_iter(Vec::<TestInt>::new());
+    assert_eq!(hasher.into_revision(), Vec::<TestInt>::new().get_revision());
+}
+
+#[test]
+fn revision_hash_combine() {
+    let a = TestInt(1).get_revision();
+    let b = TestInt(2).get_revision();
+    let c = TestInt(3).get_revision();
+
+    assert_eq!(
+        RevisionHash::combine([a, b, c]),
+        RevisionHash::combine([a, b, c])
+    );
+    assert_ne!(
+        RevisionHash::combine([a, b, c]),
+        RevisionHash::combine([c, b, a])
+    );
+    assert_eq!(
+        RevisionHash::combine([a, b, c]),
+        vec![TestInt(1), TestInt(2), TestInt(3)].get_revision()
+    );
+
+    assert_eq!(
+        RevisionHash::combine_unordered([a, b, c]),
+        RevisionHash::combine_unordered([c, a, b])
+    );
+    assert_ne!(
+        RevisionHash::combine_unordered([a, a]),
+        RevisionHash::combine_unordered([b, b])
+    );
+    assert_ne!(
+        RevisionHash::combine_unordered([a, b]),
+        RevisionHash::combine([a, b])
+    );
+}
+
+/// Type whose revision changes every time it's computed
+#[cfg(all(feature = "debug-purity-checks", debug_assertions))]
+struct TestImpure(Cell<u64>);
+
+#[cfg(all(feature = "debug-purity-checks", debug_assertions))]
+impl Revisable for TestImpure {
+    fn get_revision(&self) -> RevisionHash {
+        self.0.set(self.0.get() + 1);
+        RevisionHash::new(self.0.get())
+    }
+}
+
+#[cfg(all(feature = "debug-purity-checks", debug_assertions))]
+#[test]
+#[should_panic(expected = "not pure")]
+fn purity_check_catches_impure_revisable() {
+    let x = Revised::new(TestImpure(Cell::new(0)));
+    x.get_revision();
+}
+
+#[test]
+fn revision_hash_u64_conversions() {
+    let r = "stored".get_revision();
+    let stored: u64 = r.into();
+    assert_eq!(stored, r.value());
+    let loaded: RevisionHash = stored.into();
+    assert_eq!(loaded, r);
+    assert_eq!(RevisionHash::from(42), RevisionHash::new(42));
+    assert_eq!(u64::from(RevisionHash::new(42)), 42);
+}
+
+#[test]
+fn revision_hash_formatting() {
+    let r = RevisionHash::new(0xab_cdef);
+    assert_eq!(r.to_string(), "0x0000000000abcdef");
+    assert_eq!(format!("{:x}", r), "abcdef");
+    assert_eq!(format!("{:X}", r), "ABCDEF");
+    assert_eq!(format!("{:#010x}", r), "0x00abcdef");
+    assert_eq!(format!("{:?}", r), "RevisionHash(11259375)");
+
+    assert_eq!(
+        RevisionHash::new(u64::MAX).to_string(),
+        "0xffffffffffffffff"
+    );
+    assert_eq!(RevisionHash::new(0).to_string(), "0x0000000000000000");
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_revision_matches_sequential() {
+    let items: Vec<Vec<TestInt>> = (0..200_000)
+        .map(|i| vec![TestInt(i), TestInt(i * 7 % 13)])
+        .collect();
+    assert_eq!(crate::par_revision(&items), items.get_revision());
+
+    let empty: Vec<TestInt> = Vec::new();
+    assert_eq!(crate::par_revision(&empty), empty.get_revision());
+}
+
+#[test]
+#[cfg_attr(
+    all(feature = "debug-purity-checks", debug_assertions),
+    ignore = "counts revision computations, which purity checks double"
+)]
+fn revised_slice_incremental() {
+    let counter = Rc::new(Cell::new(0));
+    let mut slice: RevisedSlice<CountedInt> =
+        (0..100).map(|i| CountedInt::new(i, &counter)).collect();
+    let plain = |slice: &RevisedSlice<CountedInt>| {
+        slice
+            .iter()
+            .map(|x| TestInt(x.value))
+            .collect::<Vec<_>>()
+            .get_revision()
+    };
+
+    let r0 = slice.get_revision();
+    assert_eq!(r0, plain(&slice));
+    assert_eq!(counter.get(), 100);
+
+    // Reading again doesn't rehash anything
+    assert_eq!(slice.get_revision(), r0);
+    assert_eq!(counter.get(), 100);
+
+    // Editing one item only rehashes that item
+    slice.get_mut(42).unwrap().value = -1;
+    let r1 = slice.get_revision();
+    assert_ne!(r1, r0);
+    assert_eq!(r1, plain(&slice));
+    assert_eq!(counter.get(), 101);
+
+    slice.push(CountedInt::new(100, &counter));
+    assert_eq!(slice.get_revision(), plain(&slice));
+    assert_eq!(counter.get(), 102);
+
+    assert_eq!(slice.pop().unwrap().value, 100);
+    assert_eq!(slice.get_revision(), r1);
+    assert_eq!(counter.get(), 102);
+}
+
+#[test]
+fn revised_slice_matches_revised_vec() {
+    let mut vec: RevisedVec<TestInt> = (0..50).map(|i| Revised::new(TestInt(i))).collect();
+    let mut slice: RevisedSlice<TestInt> = (0..50).map(TestInt).collect();
+    assert_eq!(vec.get_revision(), slice.get_revision());
+
+    vec[7].0 = -7;
+    slice.get_mut(7).unwrap().0 = -7;
+    assert_eq!(vec.get_revision(), slice.get_revision());
+}
+
+#[test]
+fn revised_cache_lru() {
+    let calls = Cell::new(0);
+    let f = |x: i32| {
+        calls.set(calls.get() + 1);
+        x * x
+    };
+
+    let mut cache = RevisedCache::new(2);
+    assert!(cache.is_empty());
+    assert_eq!(*cache.get_or_insert_with1(f, 2), 4);
+    assert_eq!(*cache.get_or_insert_with1(f, 3), 9);
+    assert_eq!(calls.get(), 2);
+
+    // Both are hits, unlike with RevisedProperty
+    assert_eq!(*cache.get_or_insert_with1(f, 2), 4);
+    assert_eq!(*cache.get_or_insert_with1(f, 3), 9);
+    assert_eq!(calls.get(), 2);
+    assert_eq!(cache.len(), 2);
+
+    // 2 is the least recently used, so it's evicted
+    assert_eq!(*cache.get_or_insert_with1(f, 4), 16);
+    assert_eq!(calls.get(), 3);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(*cache.get_or_insert_with1(f, 3), 9);
+    assert_eq!(calls.get(), 3);
+    assert_eq!(*cache.get_or_insert_with1(f, 2), 4);
+    assert_eq!(calls.get(), 4);
+
+    // Now 4 was evicted, and 3 is still cached
+    assert_eq!(*cache.get_or_insert_with1(f, 3), 9);
+    assert_eq!(calls.get(), 4);
+    assert_eq!(*cache.get_or_insert_with1(f, 4), 16);
+    assert_eq!(calls.get(), 5);
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn range_revisions() {
+    assert_eq!((0..5).get_revision(), (0..5).get_revision());
+    assert_ne!((0..5).get_revision(), (0..6).get_revision());
+    assert_ne!((0..5).get_revision(), (1..5).get_revision());
+
+    // The kind of range matters
+    assert_ne!((0..5).get_revision(), (0..=5).get_revision());
+    assert_ne!((0..5_i64).get_revision(), (0..=4_i64).get_revision());
+    assert_ne!((..5).get_revision(), (..=5).get_revision());
+    assert_ne!((5..).get_revision(), (..5).get_revision());
+
+    assert_eq!((0..=5_i64).get_revision(), (0..=5_i64).get_revision());
+}
+
+#[test]
+fn time_revisions() {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    assert_eq!(
+        Duration::from_millis(1500).get_revision(),
+        Duration::new(1, 500_000_000).get_revision()
+    );
+    assert_ne!(
+        Duration::from_millis(1500).get_revision(),
+        Duration::from_millis(1501).get_revision()
+    );
+    assert_ne!(
+        Duration::from_secs(1).get_revision(),
+        Duration::from_nanos(1).get_revision()
+    );
+
+    let offset = Duration::from_secs(60);
+    let after = UNIX_EPOCH + offset;
+    let before = UNIX_EPOCH - offset;
+    assert_eq!(after.get_revision(), (UNIX_EPOCH + offset).get_revision());
+    assert_ne!(after.get_revision(), before.get_revision());
+
+    let now = SystemTime::now();
+    assert_ne!(
+        now.get_revision(),
+        (now + Duration::from_nanos(1)).get_revision()
+    );
+}
+
+#[test]
+fn hash_as_revision() {
+    let a = HashAsRevision((7_u32, String::from("seven")));
+    let b = HashAsRevision((7_u32, String::from("seven")));
+    let c = HashAsRevision((7_u32, String::from("eight")));
+    let d = HashAsRevision((8_u32, String::from("seven")));
+    assert_eq!(a.get_revision(), b.get_revision());
+    assert_ne!(a.get_revision(), c.get_revision());
+    assert_ne!(a.get_revision(), d.get_revision());
+
+    let mut hasher = RevisionHasher::new();
+    (7_u32, String::from("seven")).hash(&mut hasher);
+    assert_eq!(a.get_revision(), hasher.into_revision());
+    assert_eq!(a.into_inner().1, "seven");
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_generic_bounds() {
+    /// Not Revisable
+    struct Handle;
+
+    trait Store {
+        type Key;
+    }
+
+    struct HandleStore;
+
+    impl Store for HandleStore {
+        type Key = u32;
+    }
+
+    // Only T needs to be Revisable, since C is only used by a skipped field
+    #[derive(crate::Revisable)]
+    struct Wrapper<T, C> {
+        inner: Vec<T>,
+        #[revise(skip)]
+        cache: C,
+    }
+
+    // The inferred bound S: Revisable would rule out HandleStore
+    #[derive(crate::Revisable)]
+    #[revise(bound = "S::Key: Revisable")]
+    struct Keyed<S: Store> {
+        key: S::Key,
+    }
+
+    let wrapper = Wrapper {
+        inner: vec![1, 2],
+        cache: Handle,
+    };
+    let _ = &wrapper.cache;
+    let mut hasher = RevisionHasher::new();
+    hasher.write_revisable(&vec![1, 2]);
+    assert_eq!(wrapper.get_revision(), hasher.into_revision());
+
+    let keyed: Keyed<HandleStore> = Keyed { key: 7 };
+    let mut hasher = RevisionHasher::new();
+    hasher.write_revisable(&7_u32);
+    assert_eq!(keyed.get_revision(), hasher.into_revision());
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_vec_field() {
+    #[derive(crate::Revisable)]
+    struct Row {
+        cells: Vec<TestInt>,
+    }
+
+    fn revision_of<T: Revisable>(value: &T) -> RevisionHash {
+        value.get_revision()
+    }
+
+    let row = Row {
+        cells: vec![TestInt(1), TestInt(2)],
+    };
+    let mut hasher = RevisionHasher::new();
+    hasher.write_revision([TestInt(1), TestInt(2)][..].get_revision());
+    assert_eq!(row.get_revision(), hasher.into_revision());
+    assert_eq!(revision_of(&row.cells), row.cells[..].get_revision());
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_phantom_data() {
+    use std::marker::PhantomData;
+
+    #[derive(crate::Revisable)]
+    struct Tagged<T> {
+        id: u32,
+        marker: PhantomData<T>,
+    }
+
+    let a: Tagged<i32> = Tagged {
+        id: 1,
+        marker: PhantomData,
+    };
+    let b: Tagged<u8> = Tagged {
+        id: 1,
+        marker: PhantomData,
+    };
+    let c: Tagged<u8> = Tagged {
+        id: 2,
+        marker: PhantomData,
+    };
+    assert_eq!(a.get_revision(), b.get_revision());
+    assert_ne!(b.get_revision(), c.get_revision());
+
+    // T only appears in PhantomData, so it needn't be Revisable
+    struct Handle;
+    let d: Tagged<Handle> = Tagged {
+        id: 1,
+        marker: PhantomData,
+    };
+    assert_eq!(a.get_revision(), d.get_revision());
+
+    assert_eq!(PhantomData::<str>.get_revision(), ().get_revision());
+}
+
+/// Poll a future to completion on the current thread, for futures which
+/// never actually wait
+fn test_block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+    loop {
+        if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn revised_property_refresh_async() {
+    let calls = Cell::new(0);
+    let f = |x: i32| {
+        calls.set(calls.get() + 1);
+        async move { x + 1 }
+    };
+
+    let mut property = RevisedProperty::new();
+    test_block_on(property.refresh1_async(f, 1));
+    assert_eq!(property.get_cached(), Some(&2));
+    assert_eq!(calls.get(), 1);
+
+    test_block_on(property.refresh1_async(f, 1));
+    assert_eq!(calls.get(), 1);
+
+    test_block_on(property.refresh1_async(f, 5));
+    assert_eq!(property.get_cached(), Some(&6));
+    assert_eq!(calls.get(), 2);
+
+    // Shares its cache key with refresh1
+    property.refresh1(|_: i32| -> i32 { unreachable!() }, 5);
+    assert_eq!(property.get_cached(), Some(&6));
+}
+
+#[test]
+fn revised_property_refresh_checked() {
+    let calls = Cell::new(0);
+    let f = |k: TestCollidingKey| {
+        calls.set(calls.get() + 1);
+        k.1
+    };
+
+    let mut property = RevisedProperty::new_checked();
+    property.refresh1_checked(f, TestCollidingKey(1, 0));
+    property.refresh1_checked(f, TestCollidingKey(1, 0));
+    assert_eq!(calls.get(), 1);
+    property.refresh1_checked(f, TestCollidingKey(2, 0));
+    assert_eq!(calls.get(), 2);
+    assert_eq!(property.get_cached(), Some(&0));
+
+    // Borrowed arguments can be checked too
+    let keys = [TestCollidingKey(1, 0), TestCollidingKey(2, 0)];
+    let mut property = RevisedProperty::new_checked();
+    property.refresh1_checked(|k: &TestCollidingKey| k.0, &keys[0]);
+    property.refresh1_checked(|k: &TestCollidingKey| k.0, &keys[0]);
+    property.refresh1_checked(|k: &TestCollidingKey| k.0, &keys[1]);
+    assert_eq!(property.get_cached(), Some(&2));
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "collided")]
+fn revised_property_refresh_checked_collision() {
+    let mut property = RevisedProperty::new_checked();
+    property.refresh1_checked(|k: TestCollidingKey| k.1, TestCollidingKey(1, 0));
+
+    // Same revision as the previous arguments, but not equal
+    property.refresh1_checked(|k: TestCollidingKey| k.1, TestCollidingKey(1, 1));
+}
+
+#[test]
+fn revised_property_send_sync() {
+    // Must hold in debug and release builds alike
+    fn _assert_send_sync<T: Send + Sync>() {}
+    _assert_send_sync::<RevisedProperty<u32>>();
+    _assert_send_sync::<RevisedProperty<u32, u32>>();
+
+    let mut property = RevisedProperty::new_checked();
+    property.refresh1_checked(|x: u32| x + 1, 1);
+    let property = std::thread::spawn(move || property).join().unwrap();
+    assert_eq!(property.get_cached(), Some(&2));
+}
+
+/// Collection type implementing Revisable through its slice of items
+struct TestPlaylist {
+    tracks: Vec<TestInt>,
+}
+
+impl Revisable for TestPlaylist {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_revisable_slice(&self.tracks);
+        hasher.into_revision()
+    }
+}
+
+#[test]
+fn hasher_write_revisable_slice() {
+    let playlist = TestPlaylist {
+        tracks: vec![TestInt(3), TestInt(1), TestInt(4)],
+    };
+    assert_eq!(playlist.get_revision(), playlist.tracks.get_revision());
+    assert_eq!(
+        playlist.get_revision(),
+        <[TestInt] as Revisable>::get_revision(&playlist.tracks[..])
+    );
+
+    let empty = TestPlaylist { tracks: Vec::new() };
+    assert_eq!(empty.get_revision(), Vec::<TestInt>::new().get_revision());
+}
+
+#[test]
+fn revision128_basics() {
+    assert_eq!(1_i32.get_revision128(), 1_i32.get_revision128());
+    assert_ne!(1_i32.get_revision128(), 2_i32.get_revision128());
+
+    // Both halves are in use and differ from each other
+    let r = "hello".get_revision128().value();
+    assert_ne!(r >> 64, 0);
+    assert_ne!(r >> 64, r & u64::MAX as u128);
+
+    // Sequences are ordered and framed by length
+    assert_ne!(vec![1, 2].get_revision128(), vec![2, 1].get_revision128());
+    assert_ne!(
+        (vec![1], vec![2, 3]).get_revision128(),
+        (vec![1, 2], vec![3]).get_revision128()
+    );
+    assert_eq!([1, 2, 3].get_revision128(), vec![1, 2, 3].get_revision128());
+
+    // Maps and sets ignore order
+    let hash_map: HashMap<i32, String> = (0..20).map(|i| (i, i.to_string())).collect();
+    let btree_map: BTreeMap<i32, String> = (0..20).rev().map(|i| (i, i.to_string())).collect();
+    assert_eq!(hash_map.get_revision128(), btree_map.get_revision128());
+    let hash_set: HashSet<i32> = (0..20).collect();
+    let btree_set: BTreeSet<i32> = (0..20).collect();
+    assert_eq!(hash_set.get_revision128(), btree_set.get_revision128());
+}
+
+#[test]
+fn revision128_wrappers() {
+    let value = vec![1, 2, 3];
+    let expected = value.get_revision128();
+
+    // Wrappers have the same revision as their contents
+    assert_eq!(Rc::new(value.clone()).get_revision128(), expected);
+    assert_eq!(Arc::new(value.clone()).get_revision128(), expected);
+    assert_eq!(Cow::Borrowed(&value[..]).get_revision128(), expected);
+    assert_eq!(
+        Cow::<[i32]>::Owned(value.clone()).get_revision128(),
+        expected
+    );
+    assert_eq!(Revised::new(value.clone()).get_revision128(), expected);
+    assert_eq!(Cell::new(7_u8).get_revision128(), 7_u8.get_revision128());
+
+    // Ok and Err are distinguished, like Some and None
+    let ok: Result<i32, i32> = Ok(1);
+    let err: Result<i32, i32> = Err(1);
+    assert_ne!(ok.get_revision128(), err.get_revision128());
+    assert_ne!(ok.get_revision128(), 1.get_revision128());
+    assert_ne!(ok.get_revision128(), Some(1).get_revision128());
+}
+
+#[test]
+fn revision128_map_entries_dont_cancel() {
+    // Same as map_revision_entries_dont_cancel, since two entries with
+    // the same entry hash would cancel out if combined with xor
+    let a: BTreeMap<TestCollidingKey, i32> =
+        [(TestCollidingKey(1, 0), 5), (TestCollidingKey(1, 1), 5)]
+            .into_iter()
+            .collect();
+    let b: BTreeMap<TestCollidingKey, i32> =
+        [(TestCollidingKey(2, 0), 7), (TestCollidingKey(2, 1), 7)]
+            .into_iter()
+            .collect();
+    assert_ne!(a.get_revision128(), b.get_revision128());
+
+    let empty: BTreeMap<TestCollidingKey, i32> = BTreeMap::new();
+    assert_ne!(a.get_revision128(), empty.get_revision128());
+}
+
+#[test]
+fn revise_ordered_and_unordered() {
+    let items = vec![TestInt(1), TestInt(2), TestInt(3)];
+    assert_eq!(revise_ordered(&items), items.get_revision());
+    assert_eq!(revise_ordered(items.iter().rev()), {
+        let reversed: Vec<&TestInt> = items.iter().rev().collect();
+        reversed.get_revision()
+    });
+    assert_ne!(revise_ordered(items.iter().rev()), items.get_revision());
+    assert_eq!(
+        revise_ordered(Vec::<TestInt>::new()),
+        Vec::<TestInt>::new().get_revision()
+    );
+    assert_eq!(revise_ordered(&items), revision_of_ordered_set(&items));
+
+    let set: HashSet<i32> = [1, 2, 3].into_iter().collect();
+    assert_eq!(revise_unordered([3, 1, 2]), set.get_revision());
+    assert_eq!(revise_unordered([1, 2, 3]), revise_unordered([2, 3, 1]));
+
+    // Repeated items count, like in a multiset
+    assert_ne!(revise_unordered([1, 1, 2]), revise_unordered([1, 2]));
+    assert_ne!(revise_unordered([1, 1]), revise_unordered([2, 2]));
+}
+
+#[test]
+fn revision_hasher_with_seed() {
+    let hash_with = |mut hasher: RevisionHasher| {
+        hasher.write(b"contents");
+        hasher.into_revision()
+    };
+    let config = hash_with(RevisionHasher::with_seed(1, 2, 3, 4));
+    let document = hash_with(RevisionHasher::with_seed(5, 6, 7, 8));
+    assert_ne!(config, document);
+    assert_eq!(config, hash_with(RevisionHasher::with_seed(1, 2, 3, 4)));
+    assert_ne!(config, hash_with(RevisionHasher::new()));
+}
+
+#[test]
+fn revised_property_borrowed_args() {
+    let calls = Cell::new(0);
+    let f = |name: Option<&String>, values: &[i32]| {
+        calls.set(calls.get() + 1);
+        name.map_or(0, |n| n.len()) + values.len()
+    };
+
+    let name = String::from("scene");
+    let mut values = vec![1, 2, 3];
+
+    let mut property = RevisedProperty::new();
+    property.refresh2(f, Some(&name), &values[..]);
+    assert_eq!(calls.get(), 1);
+    assert_eq!(property.get_cached(), Some(&8));
+
+    // Borrowed arguments hash the same as their owned counterparts
+    assert_eq!(
+        (Some(&name), &values[..]).get_revision(),
+        (Some(name.clone()), values.clone()).get_revision()
+    );
+
+    property.refresh2(f, Some(&name), &values[..]);
+    assert_eq!(calls.get(), 1);
+
+    values.push(4);
+    property.refresh2(f, Some(&name), &values[..]);
+    assert_eq!(calls.get(), 2);
+    assert_eq!(property.get_cached(), Some(&9));
+
+    property.refresh2(f, None, &values[..]);
+    assert_eq!(calls.get(), 3);
+    assert_eq!(property.get_cached(), Some(&4));
+}
+
+#[test]
+fn revision_watcher_transitions() {
+    let mut watcher = RevisionWatcher::new();
+    assert_eq!(watcher.last_seen(), None);
+
+    let mut value = vec![1, 2, 3];
+    assert!(watcher.changed_since_last(&value));
+    assert_eq!(watcher.last_seen(), Some(value.get_revision()));
+    assert!(!watcher.changed_since_last(&value));
+
+    value.push(4);
+    assert!(watcher.changed_since_last(&value));
+    assert!(!watcher.changed_since_last(&value));
+
+    // Changing back is a change too
+    value.pop();
+    assert!(watcher.changed_since_last(&value));
+}
+
+#[test]
+fn revised_property_refresh_ref() {
+    let calls = Cell::new(0);
+    let f = |scene: &Vec<i32>| {
+        calls.set(calls.get() + 1);
+        scene.iter().sum::<i32>()
+    };
+
+    let mut scene = vec![1, 2, 3];
+    let mut property = RevisedProperty::new();
+    property.refresh1_ref(f, &scene);
+    property.refresh1_ref(f, &scene);
+    assert_eq!(calls.get(), 1);
+    assert_eq!(property.get_cached(), Some(&6));
+
+    // The argument is still owned by the caller and can be changed
+    scene.push(4);
+    property.refresh1_ref(f, &scene);
+    assert_eq!(calls.get(), 2);
+    assert_eq!(property.get_cached(), Some(&10));
+
+    let g = |scene: &Vec<i32>, name: &str, scale: &i32| {
+        calls.set(calls.get() + 1);
+        scene.len() as i32 + name.len() as i32 * scale
+    };
+    let mut property = RevisedProperty::new();
+    property.refresh3_ref(g, &scene, "ab", &10);
+    property.refresh3_ref(g, &scene, "ab", &10);
+    assert_eq!(calls.get(), 3);
+    assert_eq!(property.get_cached(), Some(&24));
+
+    let h = |a: &[i32], b: &str| a.len() + b.len();
+    let mut property = RevisedProperty::new();
+    property.refresh2_ref(h, &scene[..], "abc");
+    assert_eq!(property.get_cached(), Some(&7));
+}
+
+#[test]
+fn interned_unsized_smart_pointers() {
+    let items: Arc<[TestInt]> = vec![TestInt(1), TestInt(2)].into();
+    assert_eq!(
+        items.get_revision(),
+        vec![TestInt(1), TestInt(2)].get_revision()
+    );
+    let shared = Arc::clone(&items);
+    assert_eq!(shared.get_revision(), items.get_revision());
+
+    let name: Rc<str> = "interned".into();
+    assert_eq!(name.get_revision(), "interned".get_revision());
+    assert_ne!(name.get_revision(), Rc::<str>::from("other").get_revision());
+
+    let rc_items: Rc<[i32]> = Rc::from([1, 2, 3]);
+    let arc_name: Arc<str> = Arc::from("interned");
+    assert_eq!(rc_items.get_revision(), [1, 2, 3].get_revision());
+    assert_eq!(arc_name.get_revision(), name.get_revision());
+}
+
+#[test]
+fn revised_fast_revision() {
+    let counter = Rc::new(Cell::new(0));
+    let mut item = Revised::new(CountedInt::new(1, &counter));
+    let r0 = item.fast_revision();
+    assert_eq!(r0, TestInt(1).get_revision());
+
+    let computed = counter.get();
+    for _ in 0..10 {
+        assert_eq!(item.fast_revision(), r0);
+        assert_eq!(Revisable::fast_revision(&&item), r0);
+        assert_eq!(Box::new(&item).fast_revision(), r0);
+    }
+    assert_eq!(counter.get(), computed);
+
+    item.get_mut().value = 2;
+    assert_eq!(item.fast_revision(), TestInt(2).get_revision());
+
+    // Types without a cache default to get_revision
+    assert_eq!(TestInt(2).fast_revision(), TestInt(2).get_revision());
+}
+
+struct TestLayoutV1(i32);
+struct TestLayoutV2(i32);
+
+impl Revisable for TestLayoutV1 {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_tag("layout_v1");
+        hasher.write_i32(self.0);
+        hasher.into_revision()
+    }
+}
+
+impl Revisable for TestLayoutV2 {
+    fn get_revision(&self) -> RevisionHash {
+        let mut hasher = RevisionHasher::new();
+        hasher.write_tag("layout_v2");
+        hasher.write_i32(self.0);
+        hasher.into_revision()
+    }
+}
+
+#[test]
+fn revision_hasher_write_tag() {
+    assert_ne!(
+        TestLayoutV1(1).get_revision(),
+        TestLayoutV2(1).get_revision()
+    );
+    assert_eq!(
+        TestLayoutV2(1).get_revision(),
+        TestLayoutV2(1).get_revision()
+    );
+    assert_ne!(TestLayoutV1(1).get_revision(), TestInt(1).get_revision());
+
+    // The length prefix keeps the tag apart from what follows
+    let mut a = RevisionHasher::new();
+    a.write_tag("ab");
+    a.write(b"c");
+    let mut b = RevisionHasher::new();
+    b.write_tag("a");
+    b.write(b"bc");
+    assert_ne!(a.into_revision(), b.into_revision());
+}
+
+#[test]
+fn revised_notify_only_on_change() {
+    let notified: Rc<Cell<Option<RevisionHash>>> = Rc::new(Cell::new(None));
+    let count = Rc::new(Cell::new(0));
+    let (last, calls) = (Rc::clone(&notified), Rc::clone(&count));
+    let mut item = RevisedNotify::new(vec![1, 2, 3], move |r| {
+        last.set(Some(r));
+        calls.set(calls.get() + 1);
+    });
+
+    let r0 = item.get_revision();
+    assert_eq!(count.get(), 0);
+
+    // Mutating to the same value doesn't notify
+    item.push(4);
+    item.pop();
+    assert_eq!(item.get_revision(), r0);
+    item[0] = 1;
+    assert_eq!(item.get_revision(), r0);
+    assert_eq!(count.get(), 0);
+
+    // A real change notifies once, however often the revision is read
+    item[0] = 10;
+    let r1 = item.get_revision();
+    item.get_revision();
+    assert_ne!(r1, r0);
+    assert_eq!(count.get(), 1);
+    assert_eq!(notified.get(), Some(r1));
+}
+
+#[test]
+fn revised_property_chain() {
+    let b_calls = Cell::new(0);
+    let c_calls = Cell::new(0);
+    let to_a = |x: i32| x + 1;
+    let to_b = |a: &i32| {
+        b_calls.set(b_calls.get() + 1);
+        a / 10
+    };
+    let to_c = |b: &i32| {
+        c_calls.set(c_calls.get() + 1);
+        b * 2
+    };
+
+    let mut a = RevisedProperty::new();
+    let mut b = RevisedProperty::new();
+    let mut c = RevisedProperty::new();
+    let mut refresh_all = |x: i32| {
+        a.refresh1(to_a, x);
+        b.refresh_from(to_b, &a);
+        c.refresh_from(to_c, &b.by_output());
+        *c.get_cached().unwrap()
+    };
+
+    assert_eq!(refresh_all(11), 2);
+    assert_eq!((b_calls.get(), c_calls.get()), (1, 1));
+
+    // Unchanged input: nothing is recomputed
+    assert_eq!(refresh_all(11), 2);
+    assert_eq!((b_calls.get(), c_calls.get()), (1, 1));
+
+    // A changed and B was recomputed, but B's output stayed the same
+    assert_eq!(refresh_all(14), 2);
+    assert_eq!((b_calls.get(), c_calls.get()), (2, 1));
+
+    assert_eq!(refresh_all(24), 4);
+    assert_eq!((b_calls.get(), c_calls.get()), (3, 2));
+}
+
+#[test]
+fn revised_property_mixed_lifetime_args() {
+    struct Mesh {
+        vertices: Vec<i32>,
+    }
+    struct Material {
+        name: String,
+    }
+
+    // Each elided lifetime is distinct
+    fn memoize(property: &mut RevisedProperty<usize>, vertices: &[i32], name: &str, scale: &i32) {
+        assert_eq!(
+            (vertices, name, scale).get_revision(),
+            (vertices.to_vec(), name.to_string(), *scale).get_revision()
+        );
+        property.refresh3(
+            |v: &[i32], n: &str, s: &i32| v.len() + n.len() * *s as usize,
+            vertices,
+            name,
+            scale,
+        );
+    }
+
+    let mesh = Mesh {
+        vertices: vec![1, 2, 3],
+    };
+    let mut property = RevisedProperty::new();
+    {
+        let material = Material {
+            name: String::from("steel"),
+        };
+        let scale = 2;
+        memoize(&mut property, &mesh.vertices, &material.name, &scale);
+    }
+    assert_eq!(property.get_cached(), Some(&13));
+}
+
+#[test]
+fn revision_hash_null() {
+    assert!(RevisionHash::NULL.is_null());
+    assert!(RevisionHash::new(0).is_null());
+    assert!(!TestInt(0).get_revision().is_null());
+
+    // Hashing nothing at all
+    assert!(!RevisionHasher::new().into_revision().is_null());
+    assert!(!().get_revision().is_null());
+    assert!(!"".get_revision().is_null());
+    assert!(!Vec::<i32>::new().get_revision().is_null());
+    assert!(!HashMap::<i32, i32>::new().get_revision().is_null());
+    assert!(!HashSet::<i32>::new().get_revision().is_null());
+    assert!(!RevisionHash::combine([]).is_null());
+    assert!(!RevisionHash::unit_variant(0).is_null());
+}
+
+#[test]
+fn path_revision() {
+    use std::path::{Path, PathBuf};
+
+    let a = Path::new("assets/textures/stone.png");
+    let b = PathBuf::from("assets/textures/stone.png");
+    let c = Path::new("assets/textures/grass.png");
+    assert_eq!(a.get_revision(), b.get_revision());
+    assert_ne!(a.get_revision(), c.get_revision());
+
+    // Length-prefixed, so adjacent paths don't run together
+    assert_ne!(
+        (Path::new("ab"), Path::new("c")).get_revision(),
+        (Path::new("a"), Path::new("bc")).get_revision()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn path_revision_non_utf8() {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt, path::Path};
+
+    let a = Path::new(OsStr::from_bytes(b"file\xff"));
+    let b = Path::new(OsStr::from_bytes(b"file\xfe"));
+    assert_ne!(a.get_revision(), b.get_revision());
+}
+
+#[test]
+fn net_address_revisions() {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    let v4 = Ipv4Addr::new(1, 2, 3, 4);
+    assert_eq!(v4.get_revision(), Ipv4Addr::new(1, 2, 3, 4).get_revision());
+    assert_ne!(v4.get_revision(), Ipv4Addr::new(1, 2, 3, 5).get_revision());
+
+    let v6: Ipv6Addr = "2001:db8::1".parse().unwrap();
+    assert_ne!(v6.get_revision(), Ipv6Addr::LOCALHOST.get_revision());
+
+    // A v4 address and its v4-mapped v6 address are distinct
+    let mapped = IpAddr::V6(v4.to_ipv6_mapped());
+    assert_ne!(IpAddr::V4(v4).get_revision(), mapped.get_revision());
+
+    let a = SocketAddr::new(IpAddr::V4(v4), 8080);
+    assert_eq!(
+        a.get_revision(),
+        "1.2.3.4:8080".parse::<SocketAddr>().unwrap().get_revision()
+    );
+    assert_ne!(
+        a.get_revision(),
+        SocketAddr::new(IpAddr::V4(v4), 8081).get_revision()
+    );
+    assert_ne!(
+        a.get_revision(),
+        SocketAddr::new(mapped, 8080).get_revision()
+    );
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_with_function() {
+    /// Not Revisable
+    struct Buffer {
+        id: u64,
+        mapped: bool,
+    }
+
+    fn hash_buffer(buffer: &Buffer, hasher: &mut RevisionHasher) {
+        hasher.write_u64(buffer.id);
+    }
+
+    #[derive(crate::Revisable)]
+    struct Mesh<B> {
+        vertices: Vec<TestInt>,
+        #[revise(with = "hash_buffer")]
+        buffer: Buffer,
+        #[revise(with = "crate::test::hash_any_handle")]
+        handle: B,
+    }
+
+    // B needn't be Revisable, since the field is hashed by a function
+    struct Token;
+
+    let mesh = |id, mapped| Mesh {
+        vertices: vec![TestInt(1)],
+        buffer: Buffer { id, mapped },
+        handle: Token,
+    };
+    let _ = mesh(0, false).buffer.mapped;
+
+    // The function's output replaces the field's revision
+    let mut hasher = RevisionHasher::new();
+    hasher.write_revisable(&vec![TestInt(1)]);
+    hasher.write_u64(3);
+    assert_eq!(mesh(3, false).get_revision(), hasher.into_revision());
+    assert_eq!(mesh(3, false).get_revision(), mesh(3, true).get_revision());
+    assert_ne!(mesh(3, false).get_revision(), mesh(4, false).get_revision());
+}
+
+/// Hash function for fields of any type, which hashes nothing
+#[cfg(feature = "derive")]
+fn hash_any_handle<B>(_handle: &B, _hasher: &mut RevisionHasher) {}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_variant_count_tag() {
+    #[derive(crate::Revisable)]
+    #[revise(variant_count_tag)]
+    enum ShapeV1 {
+        Empty,
+        Circle(i32),
+    }
+
+    #[derive(crate::Revisable)]
+    #[revise(variant_count_tag)]
+    #[allow(dead_code)]
+    enum ShapeV2 {
+        Empty,
+        Circle(i32),
+        Square(i32),
+    }
+
+    #[derive(crate::Revisable)]
+    #[allow(dead_code)]
+    enum UntaggedV1 {
+        Empty,
+        Circle(i32),
+    }
+
+    #[derive(crate::Revisable)]
+    #[allow(dead_code)]
+    enum UntaggedV2 {
+        Empty,
+        Circle(i32),
+        Square(i32),
+    }
+
+    // Adding a variant changes the revisions of the existing variants
+    assert_ne!(
+        ShapeV1::Circle(3).get_revision(),
+        ShapeV2::Circle(3).get_revision()
+    );
+    assert_ne!(ShapeV1::Empty.get_revision(), ShapeV2::Empty.get_revision());
+
+    // Without the tag, they stay the same
+    assert_eq!(
+        UntaggedV1::Circle(3).get_revision(),
+        UntaggedV2::Circle(3).get_revision()
+    );
+    assert_eq!(
+        UntaggedV1::Empty.get_revision(),
+        UntaggedV2::Empty.get_revision()
+    );
+
+    // The count is hashed before the variant's index
+    let mut hasher = RevisionHasher::new();
+    hasher.write_u32(2);
+    hasher.write_u32(1);
+    hasher.write_revisable(&3_i32);
+    assert_eq!(ShapeV1::Circle(3).get_revision(), hasher.into_revision());
+    assert_ne!(
+        ShapeV1::Circle(3).get_revision(),
+        ShapeV1::Circle(4).get_revision()
+    );
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_order_independent_field() {
+    #[derive(crate::Revisable)]
+    struct Post {
+        paragraphs: Vec<i32>,
+        #[revise(order_independent)]
+        tags: Vec<i32>,
+    }
+
+    let post = |paragraphs: [i32; 2], tags: [i32; 3]| Post {
+        paragraphs: paragraphs.to_vec(),
+        tags: tags.to_vec(),
+    };
+
+    // Permuting the order-independent field keeps the revision
+    assert_eq!(
+        post([1, 2], [1, 2, 3]).get_revision(),
+        post([1, 2], [3, 1, 2]).get_revision()
+    );
+
+    // Permuting a normal field changes it
+    assert_ne!(
+        post([1, 2], [1, 2, 3]).get_revision(),
+        post([2, 1], [1, 2, 3]).get_revision()
+    );
+    assert_ne!(
+        post([1, 2], [1, 2, 3]).get_revision(),
+        post([1, 2], [1, 2, 4]).get_revision()
+    );
+
+    // The elements are combined like those of a set
+    let set: HashSet<i32> = [1, 2, 3].into_iter().collect();
+    let mut hasher = RevisionHasher::new();
+    hasher.write_revisable(&vec![1, 2]);
+    hasher.write_revisable(&set);
+    assert_eq!(
+        post([1, 2], [2, 3, 1]).get_revision(),
+        hasher.into_revision()
+    );
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_skip_type() {
+    mod ast {
+        /// Source location, not Revisable
+        #[derive(Clone, Copy)]
+        pub struct Span {
+            pub start: usize,
+            pub end: usize,
+        }
+    }
+    use ast::Span;
+
+    #[derive(crate::Revisable)]
+    #[revise(skip_type = "Span")]
+    struct Ident {
+        name: String,
+        span: Span,
+    }
+
+    #[derive(crate::Revisable)]
+    #[revise(skip_type = "Span")]
+    #[allow(dead_code)]
+    enum Expr {
+        Var(Ident, ast::Span),
+        Add {
+            lhs: Box<Expr>,
+            rhs: Box<Expr>,
+            span: Span,
+        },
+    }
+
+    let var = |name: &str, start| {
+        let span = Span {
+            start,
+            end: start + name.len(),
+        };
+        Expr::Var(
+            Ident {
+                name: name.to_string(),
+                span,
+            },
+            span,
+        )
+    };
+    let add = |lhs, rhs, start| Expr::Add {
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+        span: Span { start, end: 0 },
+    };
+
+    // Only the spans differ
+    let a = add(var("x", 0), var("y", 4), 0);
+    let b = add(var("x", 10), var("y", 20), 7);
+    assert_eq!(a.get_revision(), b.get_revision());
+
+    let c = add(var("x", 0), var("z", 4), 0);
+    assert_ne!(a.get_revision(), c.get_revision());
+
+    let ident = Ident {
+        name: "x".to_string(),
+        span: Span { start: 1, end: 2 },
+    };
+    let _ = (ident.span.start, ident.span.end);
+    let mut hasher = RevisionHasher::new();
+    hasher.write_revisable(&"x".to_string());
+    assert_eq!(ident.get_revision(), hasher.into_revision());
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_variant_with_function() {
+    /// Not Revisable
+    #[allow(dead_code)]
+    struct GpuBuffer {
+        id: u64,
+        mapped: bool,
+    }
+
+    fn hash_gpu(buffer: &GpuBuffer, layer: &u8, hasher: &mut RevisionHasher) {
+        hasher.write_u64(buffer.id);
+        hasher.write_u8(*layer);
+    }
+
+    fn hash_nothing(_hasher: &mut RevisionHasher) {}
+
+    #[derive(crate::Revisable)]
+    enum Image<H> {
+        Cpu(Vec<u8>),
+        #[revise(variant_with = "hash_gpu")]
+        Gpu(GpuBuffer, u8),
+        #[revise(variant_with = "crate::test::hash_any_handle")]
+        External {
+            handle: H,
+        },
+        #[revise(variant_with = "hash_nothing")]
+        Blank,
+    }
+
+    // H needn't be Revisable, since its variant is hashed by a function
+    struct Token;
+
+    let gpu = |id, mapped, layer| Image::<Token>::Gpu(GpuBuffer { id, mapped }, layer);
+    let _ = Image::External { handle: Token };
+
+    // The variant's index is followed by the function's output
+    let mut hasher = RevisionHasher::new();
+    hasher.write_u32(1);
+    hasher.write_u64(7);
+    hasher.write_u8(2);
+    assert_eq!(gpu(7, false, 2).get_revision(), hasher.into_revision());
+    assert_eq!(
+        gpu(7, false, 2).get_revision(),
+        gpu(7, true, 2).get_revision()
+    );
+    assert_ne!(
+        gpu(7, false, 2).get_revision(),
+        gpu(8, false, 2).get_revision()
+    );
+    assert_ne!(
+        gpu(7, false, 2).get_revision(),
+        gpu(7, false, 3).get_revision()
+    );
+
+    // Variants using a function always hash their index like the others
+    let mut hasher = RevisionHasher::new();
+    hasher.write_u32(3);
+    assert_eq!(Image::<Token>::Blank.get_revision(), hasher.into_revision());
+    assert_ne!(
+        Image::External { handle: Token }.get_revision(),
+        Image::<Token>::Blank.get_revision()
+    );
+    assert_ne!(
+        Image::<Token>::Cpu(vec![1]).get_revision(),
+        gpu(1, false, 0).get_revision()
+    );
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_redacted_field() {
+    #[derive(crate::Revisable)]
+    struct Config {
+        endpoint: String,
+        #[revise(redact)]
+        api_key: String,
+        retries: u32,
+    }
+
+    #[derive(crate::Revisable)]
+    #[allow(dead_code)]
+    enum Auth {
+        Anonymous,
+        Token {
+            user: u32,
+            #[revise(redact)]
+            secret: u64,
+        },
+    }
+
+    let config = |key: &str| Config {
+        endpoint: "https://example.com".to_string(),
+        api_key: key.to_string(),
+        retries: 3,
+    };
+
+    // Changing the secret changes only the full revision
+    assert_ne!(config("a").full_revision(), config("b").full_revision());
+    assert_eq!(config("a").public_revision(), config("b").public_revision());
+    assert_eq!(config("a").public_revision(), config("a").get_revision());
+    assert_ne!(config("a").public_revision(), config("a").full_revision());
+
+    // The full revision hashes every field in declaration order
+    let mut hasher = RevisionHasher::new();
+    hasher.write_revisable(&"https://example.com".to_string());
+    hasher.write_revisable(&"a".to_string());
+    hasher.write_revisable(&3_u32);
+    assert_eq!(config("a").full_revision(), hasher.into_revision());
+
+    let token = |secret| Auth::Token { user: 1, secret };
+    assert_ne!(token(1).full_revision(), token(2).full_revision());
+    assert_eq!(token(1).public_revision(), token(2).public_revision());
+    assert_eq!(
+        Auth::Anonymous.full_revision(),
+        Auth::Anonymous.public_revision()
+    );
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_bytes_variant() {
+    #[derive(crate::Revisable)]
+    enum Message {
+        Ping(Vec<u8>),
+        Text(String),
+        Unknown(#[revise(bytes)] Vec<u8>),
+    }
+
+    let payload = b"\x01\x02\x03".to_vec();
+
+    // Equal payloads hash equal, and the bytes are written in one go
+    assert_eq!(
+        Message::Unknown(payload.clone()).get_revision(),
+        Message::Unknown(payload.clone()).get_revision()
+    );
+    assert_ne!(
+        Message::Unknown(payload.clone()).get_revision(),
+        Message::Unknown(b"\x01\x02".to_vec()).get_revision()
+    );
+    let mut hasher = RevisionHasher::new();
+    hasher.write_u32(2);
+    hasher.write_usize(3);
+    hasher.write(&payload);
+    assert_eq!(
+        Message::Unknown(payload.clone()).get_revision(),
+        hasher.into_revision()
+    );
+
+    // A known variant carrying the same bytes doesn't collide
+    assert_ne!(
+        Message::Unknown(payload.clone()).get_revision(),
+        Message::Ping(payload.clone()).get_revision()
+    );
+    assert_ne!(
+        Message::Unknown(payload.clone()).get_revision(),
+        Message::Text("\u{1}\u{2}\u{3}".to_string()).get_revision()
+    );
+}
+
 // TODO: more tests
@@ -0,0 +1,285 @@
+//! Fine-grained reactivity with automatic dependency tracking.
+//!
+//! A Signal holds a source value, while a Computed derives its value from
+//! other signals using a closure. While a Computed's closure runs, every
+//! Signal or Computed whose revision or value it reads is recorded as a
+//! dependency. The closure is only called again once the revision of one
+//! of those recorded dependencies has changed.
+
+use std::{
+    cell::{Ref, RefCell},
+    rc::Rc,
+};
+
+use crate::{sequence_revision, Revisable, Revised, RevisedProperty, RevisionHash};
+
+/// Something that was read during a scope and whose current revision can
+/// be queried later, without recording it again
+trait Dependency {
+    fn current_revision(&self) -> RevisionHash;
+}
+
+thread_local! {
+    /// The stack of active scopes, each with the dependencies read so far
+    static SCOPES: RefCell<Vec<Vec<Rc<dyn Dependency>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Record a dependency in the innermost active scope, if any
+fn record(dependency: Rc<dyn Dependency>) {
+    SCOPES.with(|scopes| {
+        if let Some(scope) = scopes.borrow_mut().last_mut() {
+            if !scope.iter().any(|d| Rc::ptr_eq(d, &dependency)) {
+                scope.push(dependency);
+            }
+        }
+    });
+}
+
+/// Pops the innermost scope when dropped, even if the closure panicked
+struct ScopeGuard;
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        SCOPES.with(|scopes| {
+            scopes.borrow_mut().pop();
+        });
+    }
+}
+
+/// The set of signals which were read during a call to scope, in the
+/// order in which they were first read
+pub struct Dependencies {
+    dependencies: Vec<Rc<dyn Dependency>>,
+}
+
+impl Dependencies {
+    /// Get the number of distinct signals that were read
+    pub fn len(&self) -> usize {
+        self.dependencies.len()
+    }
+
+    /// Returns true if no signals were read
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty()
+    }
+
+    /// Get the combined RevisionHash of the current revisions of all
+    /// recorded signals. This changes exactly when one of them changes.
+    /// Querying it does not record anything in an enclosing scope.
+    pub fn revision(&self) -> RevisionHash {
+        sequence_revision(self.dependencies.iter().map(|d| d.current_revision()))
+    }
+}
+
+/// Call f and return its result together with the signals whose revision
+/// or value it read. Scopes can be nested, in which case reads are only
+/// recorded in the innermost scope.
+pub fn scope<R, F: FnOnce() -> R>(f: F) -> (R, Dependencies) {
+    SCOPES.with(|scopes| scopes.borrow_mut().push(Vec::new()));
+    let guard = ScopeGuard;
+    let result = f();
+    let dependencies = SCOPES.with(|scopes| scopes.borrow_mut().last_mut().map(std::mem::take));
+    drop(guard);
+    (
+        result,
+        Dependencies {
+            dependencies: dependencies.unwrap_or_default(),
+        },
+    )
+}
+
+struct SignalInner<T> {
+    value: RefCell<Revised<T>>,
+}
+
+impl<T: Revisable> Dependency for SignalInner<T> {
+    fn current_revision(&self) -> RevisionHash {
+        self.value.borrow().get_revision()
+    }
+}
+
+/// Signal is a source value in a reactive graph. Cloning a Signal gives
+/// another handle to the same value. Reading it inside a scope, such as
+/// the closure of a Computed, records it as a dependency of that scope.
+pub struct Signal<T> {
+    inner: Rc<SignalInner<T>>,
+}
+
+impl<T: Revisable + 'static> Signal<T> {
+    /// Construct a new Signal holding the given value
+    pub fn new(value: T) -> Signal<T> {
+        Signal {
+            inner: Rc::new(SignalInner {
+                value: RefCell::new(Revised::new(value)),
+            }),
+        }
+    }
+
+    /// Get the RevisionHash of the current value, recording this signal
+    /// as a dependency of the current scope
+    pub fn get_revision(&self) -> RevisionHash {
+        self.track();
+        self.inner.current_revision()
+    }
+
+    /// Call f with the current value, recording this signal as a
+    /// dependency of the current scope
+    pub fn with<R, F: FnOnce(&T) -> R>(&self, f: F) -> R {
+        self.track();
+        f(&self.inner.value.borrow())
+    }
+
+    /// Get a copy of the current value, recording this signal as a
+    /// dependency of the current scope
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.with(T::clone)
+    }
+
+    /// Replace the value
+    pub fn set(&self, value: T) {
+        *self.inner.value.borrow_mut() = Revised::new(value);
+    }
+
+    /// Modify the value in place
+    pub fn modify<F: FnOnce(&mut T)>(&self, f: F) {
+        f(&mut self.inner.value.borrow_mut());
+    }
+
+    fn track(&self) {
+        record(Rc::clone(&self.inner) as Rc<dyn Dependency>);
+    }
+}
+
+/// Cloning a Signal<T> shares its value
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        Signal {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+/// Signal<T> is Revisable using the revision of its current value
+impl<T: Revisable + 'static> Revisable for Signal<T> {
+    fn get_revision(&self) -> RevisionHash {
+        Signal::get_revision(self)
+    }
+}
+
+struct ComputedState<T> {
+    /// The cached result, keyed on the revision of its dependencies
+    property: RevisedProperty<T>,
+
+    /// The signals read by the most recent call to the closure
+    dependencies: Dependencies,
+}
+
+struct ComputedInner<T> {
+    compute: Box<dyn Fn() -> T>,
+    state: RefCell<ComputedState<T>>,
+}
+
+impl<T> ComputedInner<T> {
+    /// Call the closure again if the revision of any recorded dependency
+    /// changed, recording the new set of dependencies
+    fn refresh(&self) {
+        let stale = {
+            let state = self.state.borrow();
+            state.property.revision != Some(state.dependencies.revision())
+        };
+        if stale {
+            let (value, dependencies) = scope(|| (self.compute)());
+            let revision = dependencies.revision();
+            let mut state = self.state.borrow_mut();
+            state.property.value = Some(value);
+            state.property.revision = Some(revision);
+            state.dependencies = dependencies;
+        }
+    }
+}
+
+impl<T> Dependency for ComputedInner<T> {
+    fn current_revision(&self) -> RevisionHash {
+        self.refresh();
+        self.state.borrow().property.revision.unwrap()
+    }
+}
+
+/// Computed is a value derived from other signals by a closure, which is
+/// called lazily when the value is read. The closure is only called again
+/// after the revision of a Signal or Computed that it read has changed,
+/// and the set of dependencies is recorded anew on each call. The closure
+/// must not read the Computed itself.
+///
+/// The RevisionHash of a Computed is the combined revision of the
+/// dependencies its closure read, so a Computed can in turn be read by
+/// other Computeds. Cloning a Computed gives another handle to the same
+/// cache.
+pub struct Computed<T> {
+    inner: Rc<ComputedInner<T>>,
+}
+
+impl<T: 'static> Computed<T> {
+    /// Construct a new Computed from a closure. The closure is not called
+    /// until the value is first read.
+    pub fn new<F: Fn() -> T + 'static>(compute: F) -> Computed<T> {
+        Computed {
+            inner: Rc::new(ComputedInner {
+                compute: Box::new(compute),
+                state: RefCell::new(ComputedState {
+                    property: RevisedProperty::new(),
+                    dependencies: Dependencies {
+                        dependencies: Vec::new(),
+                    },
+                }),
+            }),
+        }
+    }
+
+    /// Get the RevisionHash of the Computed's dependencies, recomputing
+    /// the value if needed and recording this Computed as a dependency of
+    /// the current scope
+    pub fn get_revision(&self) -> RevisionHash {
+        self.track();
+        self.inner.current_revision()
+    }
+
+    /// Borrow the up-to-date value, recomputing it if needed and recording
+    /// this Computed as a dependency of the current scope
+    pub fn get(&self) -> Ref<'_, T> {
+        self.track();
+        self.inner.refresh();
+        Ref::map(self.inner.state.borrow(), |state| {
+            state.property.get_cached().unwrap()
+        })
+    }
+
+    /// Get the number of distinct signals read by the most recent call to
+    /// the closure, or zero if it hasn't been called yet
+    pub fn dependency_count(&self) -> usize {
+        self.inner.state.borrow().dependencies.len()
+    }
+
+    fn track(&self) {
+        record(Rc::clone(&self.inner) as Rc<dyn Dependency>);
+    }
+}
+
+/// Cloning a Computed<T> shares its closure and cache
+impl<T> Clone for Computed<T> {
+    fn clone(&self) -> Self {
+        Computed {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+/// Computed<T> is Revisable using the revision of its dependencies
+impl<T: 'static> Revisable for Computed<T> {
+    fn get_revision(&self) -> RevisionHash {
+        Computed::get_revision(self)
+    }
+}